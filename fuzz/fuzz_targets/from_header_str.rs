@@ -0,0 +1,17 @@
+#![no_main]
+
+use axum_csp::CspHeaderBuilder;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(policy) = std::str::from_utf8(data) else {
+        return;
+    };
+    // anything that parses has to render, and parse back to the same thing
+    if let Ok(builder) = CspHeaderBuilder::from_header_str(policy) {
+        let rendered = builder.finish_string();
+        let reparsed =
+            CspHeaderBuilder::from_header_str(&rendered).expect("rendered policy should parse");
+        assert_eq!(reparsed.finish_string(), rendered);
+    }
+});
@@ -0,0 +1,50 @@
+//! Collecting policy requirements from several places at once
+
+use crate::{CspDirectiveType, CspHeaderBuilder, CspValue};
+use std::sync::Mutex;
+
+#[derive(Debug, Default)]
+/// A thread-safe collector for policy requirements, so independent modules can each register
+/// what they need (eg. at startup, from different threads or tasks) through a shared reference.
+///
+/// Share it in an `Arc`, then call [CspPolicyAccumulator::finalize] once everything is in.
+pub struct CspPolicyAccumulator {
+    builder: Mutex<CspHeaderBuilder>,
+}
+
+impl CspPolicyAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from an existing policy
+    pub fn from_builder(builder: CspHeaderBuilder) -> Self {
+        Self {
+            builder: Mutex::new(builder),
+        }
+    }
+
+    /// Add values to a directive, like [CspHeaderBuilder::add]
+    pub fn add(&self, directive: CspDirectiveType, values: Vec<CspValue>) {
+        let mut builder = self.lock();
+        *builder = std::mem::take(&mut *builder).add(directive, values);
+    }
+
+    /// Merge in a whole policy, like [CspHeaderBuilder::merge]
+    pub fn merge(&self, other: CspHeaderBuilder) {
+        let mut builder = self.lock();
+        *builder = std::mem::take(&mut *builder).merge(other);
+    }
+
+    /// The policy everything has added up to so far
+    pub fn finalize(&self) -> CspHeaderBuilder {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CspHeaderBuilder> {
+        // a panic mid-add can't leave the builder half-updated, so carry on with it
+        self.builder
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,222 @@
+//! Inspecting what a policy actually does
+
+use crate::{CspDirective, CspDirectiveType, CspHeaderBuilder, CspValue};
+use axum::http::HeaderValue;
+use std::collections::BTreeSet;
+
+impl CspHeaderBuilder {
+    /// Lists values which have no effect, given the other values in the same directive.
+    ///
+    /// - `'none'` is ignored by browsers when it's combined with any other source.
+    /// - `'strict-dynamic'` in `script-src`/`script-src-elem` makes browsers ignore host and scheme
+    ///   sources, `'self'` and `'unsafe-inline'`.
+    /// - A nonce or hash in a script or style directive makes browsers ignore `'unsafe-inline'`.
+    pub fn ineffective_values(&self) -> Vec<(CspDirectiveType, CspValue)> {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .collect::<Vec<&CspDirectiveType>>();
+        keys.sort();
+
+        let mut res = vec![];
+        for directive in keys {
+            let values = &self.directive_map[directive];
+
+            let strict_dynamic = matches!(
+                directive,
+                CspDirectiveType::ScriptSource | CspDirectiveType::ScriptSourceElem
+            ) && values.contains(&CspValue::StrictDynamic);
+            let nonce_or_hash = matches!(
+                directive,
+                CspDirectiveType::DefaultSrc
+                    | CspDirectiveType::ScriptSource
+                    | CspDirectiveType::ScriptSourceElem
+                    | CspDirectiveType::ScriptSourceAttr
+                    | CspDirectiveType::StyleSource
+                    | CspDirectiveType::StyleSourceElem
+                    | CspDirectiveType::StyleSourceAttr
+            ) && values.iter().any(|v| v.is_nonce_or_hash());
+
+            for value in values {
+                let ineffective = match value {
+                    CspValue::None => values.len() > 1,
+                    CspValue::SelfSite => strict_dynamic,
+                    CspValue::UnsafeInline => strict_dynamic || nonce_or_hash,
+                    value => strict_dynamic && value.is_host_or_scheme(),
+                };
+                if ineffective {
+                    res.push((*directive, value.to_owned()));
+                }
+            }
+        }
+        res
+    }
+
+    /// The policy as a browser would enforce it, with duplicate values and everything listed by
+    /// [CspHeaderBuilder::ineffective_values] removed.
+    ///
+    /// Parsing already applies the other rules browsers do, lowercasing directive names and only
+    /// keeping the first of a duplicated directive, so a parsed then normalized policy is what's
+    /// actually in force.
+    pub fn browser_normalized(mut self) -> Self {
+        for values in self.directive_map.values_mut() {
+            values.sort();
+            values.dedup();
+        }
+        for (directive, value) in self.ineffective_values() {
+            if let Some(values) = self.directive_map.get_mut(&directive) {
+                values.retain(|v| v != &value);
+            }
+        }
+        self
+    }
+
+    /// A heuristic score of how permissive the policy is, higher meaning weaker, for tracking
+    /// whether policies tighten over time. The rubric is stable and only changes in a breaking
+    /// release:
+    ///
+    /// | Finding | Points |
+    /// |---|---|
+    /// | `'unsafe-inline'` in effective `script-src`, without a nonce, hash or `'strict-dynamic'` | 25 |
+    /// | `'unsafe-eval'` in effective `script-src` | 15 |
+    /// | `*` in a directive | 20 each |
+    /// | `http:` in a directive | 10 each |
+    /// | `*`, `https:` or `http:` in effective `connect-src` | 10 |
+    /// | no effective `object-src` | 10 |
+    /// | no `frame-ancestors` | 10 |
+    /// | `'unsafe-inline'` in effective `style-src` | 5 |
+    pub fn permissiveness_score(&self) -> u32 {
+        let mut score = 0;
+        if let Some((_, values)) = self.effective_values(CspDirectiveType::ScriptSource) {
+            if values.contains(&CspValue::UnsafeInline)
+                && !values
+                    .iter()
+                    .any(|v| v.is_nonce_or_hash() || v == &CspValue::StrictDynamic)
+            {
+                score += 25;
+            }
+            if values.contains(&CspValue::UnsafeEval) {
+                score += 15;
+            }
+        }
+        for (directive, values) in &self.directive_map {
+            if !directive.takes_source_list() {
+                continue;
+            }
+            if values.iter().any(|v| v.is_wildcard()) {
+                score += 20;
+            }
+            if values.contains(&CspValue::SchemeHttp) {
+                score += 10;
+            }
+        }
+        if self
+            .effective_values(CspDirectiveType::ConnectSrc)
+            .is_some_and(|(_, values)| {
+                values.iter().any(|v| {
+                    v.is_wildcard() || matches!(v, CspValue::SchemeHttps | CspValue::SchemeHttp)
+                })
+            })
+        {
+            score += 10;
+        }
+        if self.effective_values(CspDirectiveType::ObjectSrc).is_none() {
+            score += 10;
+        }
+        if !self
+            .directive_map
+            .contains_key(&CspDirectiveType::FrameAncestors)
+        {
+            score += 10;
+        }
+        if self
+            .effective_values(CspDirectiveType::StyleSource)
+            .is_some_and(|(_, values)| values.contains(&CspValue::UnsafeInline))
+        {
+            score += 5;
+        }
+        score
+    }
+
+    /// Every host and scheme source the policy allows, across all directives, sorted and
+    /// de-duplicated. Keywords, nonces, hashes and reporting endpoints aren't included.
+    pub fn referenced_hosts(&self) -> Vec<String> {
+        let mut res: Vec<String> = self
+            .directive_map
+            .iter()
+            .filter(|(directive, _)| directive.takes_source_list())
+            .flat_map(|(_, values)| values.iter())
+            .filter(|value| value.is_host_or_scheme())
+            .map(|value| String::from(value.to_owned()))
+            .collect();
+        res.sort();
+        res.dedup();
+        res
+    }
+
+    /// Every scheme the policy allows, from scheme sources (`data:`) and hosts with an explicit
+    /// scheme (`wss://example.com`), lowercased and without the `:`.
+    pub fn allowed_schemes(&self) -> BTreeSet<String> {
+        self.directive_map
+            .iter()
+            .filter(|(directive, _)| directive.takes_source_list())
+            .flat_map(|(_, values)| values.iter())
+            .filter_map(|value| match value {
+                CspValue::SchemeHttps => Some("https".to_string()),
+                CspValue::SchemeHttp => Some("http".to_string()),
+                CspValue::SchemeData => Some("data".to_string()),
+                CspValue::SchemeOther { value } => {
+                    Some(value.trim_end_matches(':').to_ascii_lowercase())
+                }
+                CspValue::Host { value } => value
+                    .split_once("://")
+                    .map(|(scheme, _)| scheme.to_ascii_lowercase()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The legacy `X-Frame-Options` value equivalent to `frame-ancestors`, so both headers agree:
+    /// `DENY` for `'none'` and `SAMEORIGIN` for `'self'`. Anything else (or no `frame-ancestors`)
+    /// has no clean equivalent, so returns `None`.
+    pub fn recommended_frame_options(&self) -> Option<HeaderValue> {
+        match self
+            .directive_map
+            .get(&CspDirectiveType::FrameAncestors)?
+            .as_slice()
+        {
+            [CspValue::None] => Some(HeaderValue::from_static("DENY")),
+            [CspValue::SelfSite] => Some(HeaderValue::from_static("SAMEORIGIN")),
+            _ => None,
+        }
+    }
+}
+
+impl CspDirective {
+    /// Does this directive allow loading from anywhere, ie. `*` or both `https:` and `http:`?
+    /// Such a directive has no teeth, though `*` still blocks `data:`, `blob:` and inline content.
+    ///
+    /// Script and style directives (and `default-src`, which they fall back to) with
+    /// `'unsafe-inline'` and no nonce, hash or `'strict-dynamic'` count too: injected inline code
+    /// runs, which is what those directives are there to stop.
+    pub fn is_effectively_unrestricted(&self) -> bool {
+        let inline_allowed = matches!(
+            self.directive_type,
+            CspDirectiveType::DefaultSrc
+                | CspDirectiveType::ScriptSource
+                | CspDirectiveType::ScriptSourceAttr
+                | CspDirectiveType::ScriptSourceElem
+                | CspDirectiveType::StyleSource
+                | CspDirectiveType::StyleSourceAttr
+                | CspDirectiveType::StyleSourceElem
+        ) && self.values.contains(&CspValue::UnsafeInline)
+            && !self
+                .values
+                .iter()
+                .any(|v| v.is_nonce_or_hash() || v == &CspValue::StrictDynamic);
+        inline_allowed
+            || self.values.iter().any(|v| v.is_wildcard())
+            || (self.values.contains(&CspValue::SchemeHttps)
+                && self.values.contains(&CspValue::SchemeHttp))
+    }
+}
@@ -0,0 +1,88 @@
+//! Documentation that travels with a policy, since CSP has no comment syntax
+
+use crate::{CspDirective, CspDirectiveType, CspHeaderBuilder, CspValue, ParseError};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A note about a directive, or one value in a directive
+pub struct CspAnnotation {
+    pub directive: CspDirectiveType,
+    /// If this is set, the note is about this value, otherwise it's about the whole directive
+    pub value: Option<CspValue>,
+    pub note: String,
+}
+
+impl CspHeaderBuilder {
+    /// Attach a note to a directive (or one of its values). Notes are never rendered into the header.
+    pub fn annotate(
+        mut self,
+        directive: CspDirectiveType,
+        value: Option<CspValue>,
+        note: impl Into<String>,
+    ) -> Self {
+        self.annotations.push(CspAnnotation {
+            directive,
+            value,
+            note: note.into(),
+        });
+        self
+    }
+
+    /// Returns the notes attached to a directive (including those about its values)
+    pub fn annotations_for(&self, directive: CspDirectiveType) -> Vec<&CspAnnotation> {
+        self.annotations
+            .iter()
+            .filter(|a| a.directive == directive)
+            .collect()
+    }
+
+    /// Parses a header value, attaching a sidecar set of annotations which document it.
+    pub fn from_header_str_with_annotations(
+        policy: &str,
+        annotations: Vec<CspAnnotation>,
+    ) -> Result<Self, ParseError> {
+        let mut builder = Self::from_header_str(policy)?;
+        builder.annotations = annotations;
+        Ok(builder)
+    }
+
+    /// Renders the policy with its annotations as `#` comments, one directive per line, for a
+    /// docs file or PR description. This isn't a valid header, use
+    /// [CspHeaderBuilder::finish] for that.
+    ///
+    /// ```text
+    /// # catch-all
+    /// default-src 'self';
+    /// # data: inline SVG icons
+    /// img-src https: data:;
+    /// ```
+    ///
+    /// Notes about directives which aren't in the policy are left out.
+    pub fn to_documented_string(&self) -> String {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .collect::<Vec<&CspDirectiveType>>();
+        keys.sort();
+
+        let mut res = String::new();
+        for directive in keys {
+            for annotation in self.annotations_for(*directive) {
+                match &annotation.value {
+                    Some(value) => res.push_str(&format!(
+                        "# {} {}\n",
+                        String::from(value.to_owned()),
+                        annotation.note
+                    )),
+                    None => res.push_str(&format!("# {}\n", annotation.note)),
+                }
+            }
+            let mut values = self.directive_map[directive].to_owned();
+            values.sort();
+            values.dedup();
+            res.push_str(&CspDirective::from(*directive, values).to_string());
+            res.push_str(";\n");
+        }
+        res
+    }
+}
@@ -0,0 +1,145 @@
+//! Which browser versions support which parts of CSP
+//!
+//! The versions are major releases from MDN's browser compatibility data, so they're a guide
+//! rather than exact: something which shipped in a point release counts from the next major one.
+
+use crate::{CspDirectiveType, CspHeaderBuilder, CspValue};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Browser {
+    /// Also covers Chromium-based browsers like Edge
+    Chrome,
+    Firefox,
+    Safari,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+/// A browser's major version, eg. `BrowserVersion::new(Browser::Chrome, 51)`
+pub struct BrowserVersion {
+    pub browser: Browser,
+    pub major: u32,
+}
+
+impl BrowserVersion {
+    pub fn new(browser: Browser, major: u32) -> Self {
+        Self { browser, major }
+    }
+
+    /// Does this version support something first supported in `since` (per browser)?
+    fn supports(&self, since: Support) -> bool {
+        pick(since, self.browser).is_some_and(|since| self.major >= since)
+    }
+}
+
+/// The first Chrome, Firefox and Safari versions with support, if any
+type Support = (Option<u32>, Option<u32>, Option<u32>);
+
+const CSP1: Support = (Some(25), Some(23), Some(7));
+const CSP2: Support = (Some(40), Some(31), Some(10));
+/// `-elem` and `-attr` directives
+const SPLIT_DIRECTIVES: Support = (Some(75), Some(108), Some(16));
+const UNSUPPORTED: Support = (None, None, None);
+
+const fn chrome_only(chrome: u32) -> Support {
+    (Some(chrome), None, None)
+}
+
+impl CspDirectiveType {
+    /// The first version of a browser to support this directive, or `None` if it doesn't
+    pub fn supported_since(&self, browser: Browser) -> Option<u32> {
+        pick(self.support(), browser)
+    }
+
+    fn support(&self) -> Support {
+        match self {
+            CspDirectiveType::ConnectSrc
+            | CspDirectiveType::DefaultSrc
+            | CspDirectiveType::FontSrc
+            | CspDirectiveType::FrameSrc
+            | CspDirectiveType::ImgSrc
+            | CspDirectiveType::MediaSrc
+            | CspDirectiveType::ObjectSrc
+            | CspDirectiveType::ReportUri
+            | CspDirectiveType::ScriptSource
+            | CspDirectiveType::StyleSource => CSP1,
+            CspDirectiveType::Sandbox => (Some(25), Some(50), Some(7)),
+            CspDirectiveType::BaseUri => (Some(40), Some(35), Some(10)),
+            CspDirectiveType::ChildSrc => (Some(40), Some(45), Some(10)),
+            CspDirectiveType::FormAction => (Some(40), Some(36), Some(10)),
+            CspDirectiveType::FrameAncestors => (Some(40), Some(33), Some(10)),
+            CspDirectiveType::ManifestSrc => (Some(40), Some(41), Some(15)),
+            CspDirectiveType::UpgradeInsecureRequests => (Some(43), Some(42), Some(11)),
+            CspDirectiveType::BlockAllMixedContent => (Some(44), Some(48), None),
+            CspDirectiveType::WorkerSource => (Some(59), Some(58), Some(16)),
+            CspDirectiveType::ScriptSourceAttr
+            | CspDirectiveType::ScriptSourceElem
+            | CspDirectiveType::StyleSourceAttr
+            | CspDirectiveType::StyleSourceElem => SPLIT_DIRECTIVES,
+            CspDirectiveType::ReportTo => (Some(70), None, Some(17)),
+            CspDirectiveType::RequireTrustedTypesFor | CspDirectiveType::TrustedTypes => {
+                chrome_only(83)
+            }
+            CspDirectiveType::FencedFrameSrc => chrome_only(119),
+            CspDirectiveType::NavigateTo
+            | CspDirectiveType::PrefetchSrc
+            | CspDirectiveType::RequireSriFor => UNSUPPORTED,
+        }
+    }
+}
+
+impl CspValue {
+    /// The first version of a browser to support this value, or `None` if it doesn't. Hosts,
+    /// schemes, `'self'` and `'none'` are supported wherever CSP is.
+    pub fn supported_since(&self, browser: Browser) -> Option<u32> {
+        pick(self.support(), browser)
+    }
+
+    fn support(&self) -> Support {
+        match self {
+            CspValue::Nonce { .. }
+            | CspValue::NoncePlaceholder
+            | CspValue::Sha256 { .. }
+            | CspValue::Sha384 { .. }
+            | CspValue::Sha512 { .. } => CSP2,
+            CspValue::StrictDynamic => (Some(52), Some(52), Some(16)),
+            CspValue::ReportSample => (Some(59), None, Some(16)),
+            CspValue::UnsafeHashes => (Some(69), Some(121), Some(16)),
+            CspValue::WasmUnsafeEval => (Some(97), Some(102), Some(16)),
+            CspValue::InlineSpeculationRules => chrome_only(109),
+            CspValue::TrustedTypesEval => chrome_only(138),
+            CspValue::ScriptSink | CspValue::OtherSink { .. } | CspValue::AllowDuplicates => {
+                chrome_only(83)
+            }
+            CspValue::UnsafeAllowRedirects => UNSUPPORTED,
+            _ => CSP1,
+        }
+    }
+}
+
+fn pick(support: Support, browser: Browser) -> Option<u32> {
+    match browser {
+        Browser::Chrome => support.0,
+        Browser::Firefox => support.1,
+        Browser::Safari => support.2,
+    }
+}
+
+impl CspHeaderBuilder {
+    /// The policy a particular browser version actually enforces, with the directives and values
+    /// it doesn't support removed, eg. to see how much protection the oldest supported browser
+    /// gets.
+    ///
+    /// Directives are kept even if all their values are removed, since browsers treat an empty
+    /// source list as `'none'`. Unknown directives are kept as they are.
+    pub fn as_enforced_by(&self, browser: BrowserVersion) -> CspHeaderBuilder {
+        let mut res = self.clone();
+        res.directive_map
+            .retain(|directive, _| browser.supports(directive.support()));
+        for values in res.directive_map.values_mut() {
+            values.retain(|value| browser.supports(value.support()));
+        }
+        res.annotations
+            .retain(|a| browser.supports(a.directive.support()));
+        res
+    }
+}
@@ -0,0 +1,41 @@
+//! Compatibility shims for code written against older releases
+#![allow(deprecated)]
+
+use crate::{CspDirective, CspHeaderBuilder};
+use axum::http::HeaderValue;
+
+#[deprecated(
+    since = "0.0.11",
+    note = "CspSetBuilder is a compatibility shim, use CspHeaderBuilder instead"
+)]
+#[derive(Clone, Debug, Default)]
+/// The set-of-directives builder from older releases, kept so upgrading doesn't break builds.
+///
+/// Everything is delegated to a [CspHeaderBuilder], which it converts into with `.into()` once
+/// you're ready to migrate.
+pub struct CspSetBuilder {
+    builder: CspHeaderBuilder,
+}
+
+impl CspSetBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a directive, merging its values with any already there
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, directive: CspDirective) -> Self {
+        self.builder = self.builder.add(directive.directive_type, directive.values);
+        self
+    }
+
+    pub fn finish(self) -> HeaderValue {
+        self.builder.finish()
+    }
+}
+
+impl From<CspSetBuilder> for CspHeaderBuilder {
+    fn from(input: CspSetBuilder) -> CspHeaderBuilder {
+        input.builder
+    }
+}
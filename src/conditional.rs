@@ -0,0 +1,92 @@
+//! Directives which are only part of the policy while a runtime flag is set
+
+use crate::{CspDirectiveType, CspHeaderBuilder, CspValue};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+#[derive(Clone)]
+/// Values added to the policy when `condition` returns true, see
+/// [CspHeaderBuilder::add_conditional]
+pub struct CspConditional {
+    pub directive: CspDirectiveType,
+    pub values: Vec<CspValue>,
+    condition: Arc<dyn Fn() -> bool + Send + Sync>,
+}
+
+impl Debug for CspConditional {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CspConditional")
+            .field("directive", &self.directive)
+            .field("values", &self.values)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CspConditional {
+    /// Is the condition currently true?
+    pub fn is_active(&self) -> bool {
+        (self.condition)()
+    }
+}
+
+impl CspHeaderBuilder {
+    /// Adds `values` to `directive` only while `condition` returns true, eg. for a third-party
+    /// widget behind a runtime feature flag.
+    ///
+    /// Conditions are checked each time the policy is rendered.
+    /// [RouterCspExt::with_csp](crate::middleware::RouterCspExt::with_csp) renders
+    /// policies with conditional directives per request, as does
+    /// [csp_nonce_middleware](crate::middleware::csp_nonce_middleware), so they follow the flag;
+    /// a policy turned into a [HeaderValue](axum::http::HeaderValue) up front keeps whatever the
+    /// flag was then. Merging and splitting policies keeps conditional directives, but they
+    /// aren't serialized.
+    ///
+    /// ```
+    /// use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue};
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let widget_enabled = Arc::new(AtomicBool::new(false));
+    /// let flag = widget_enabled.clone();
+    /// let builder = CspHeaderBuilder::new()
+    ///     .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+    ///     .add_conditional(
+    ///         CspDirectiveType::FrameSrc,
+    ///         vec![CspValue::Host { value: "https://widget.example.com".to_string() }],
+    ///         move || flag.load(Ordering::Relaxed),
+    ///     );
+    /// assert_eq!(builder.finish_string(), "default-src 'self'");
+    /// widget_enabled.store(true, Ordering::Relaxed);
+    /// assert_eq!(
+    ///     builder.finish_string(),
+    ///     "default-src 'self'; frame-src https://widget.example.com"
+    /// );
+    /// ```
+    pub fn add_conditional(
+        mut self,
+        directive: CspDirectiveType,
+        values: Vec<CspValue>,
+        condition: impl Fn() -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.conditional_directives.push(CspConditional {
+            directive,
+            values,
+            condition: Arc::new(condition),
+        });
+        self
+    }
+
+    /// The policy as it is right now: the conditional directives whose conditions are true are
+    /// added, and the rest dropped. Analysis like [CspHeaderBuilder::validate] only looks at
+    /// unconditional directives, so use this first to check the live policy.
+    pub fn resolve_conditions(&self) -> Self {
+        let mut res = self.clone();
+        let conditionals = std::mem::take(&mut res.conditional_directives);
+        for conditional in conditionals {
+            if conditional.is_active() {
+                res = res.add(conditional.directive, conditional.values);
+            }
+        }
+        res
+    }
+}
@@ -0,0 +1,280 @@
+//! Comparing policies
+
+use crate::{CspDirectiveType, CspHeaderBuilder, CspValue};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The differences between two policies, sorted by directive then value.
+pub struct CspDiff {
+    /// Directives which didn't exist before
+    pub added_directives: Vec<CspDirectiveType>,
+    /// Directives which no longer exist
+    pub removed_directives: Vec<CspDirectiveType>,
+    pub added_values: Vec<(CspDirectiveType, CspValue)>,
+    pub removed_values: Vec<(CspDirectiveType, CspValue)>,
+    /// Unknown directives (see [CspHeaderBuilder::add_unknown]) with values which didn't exist
+    /// before, by name. A changed value is in both lists.
+    pub added_unknown_directives: Vec<(String, String)>,
+    /// Unknown directives with values which no longer exist
+    pub removed_unknown_directives: Vec<(String, String)>,
+}
+
+impl CspDiff {
+    /// Are the policies the same?
+    pub fn is_empty(&self) -> bool {
+        self.added_directives.is_empty()
+            && self.removed_directives.is_empty()
+            && self.added_values.is_empty()
+            && self.removed_values.is_empty()
+            && self.added_unknown_directives.is_empty()
+            && self.removed_unknown_directives.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// One step of a [CspPatch]
+pub enum CspPatchOp {
+    AddDirective {
+        directive: CspDirectiveType,
+        values: Vec<CspValue>,
+    },
+    RemoveDirective {
+        directive: CspDirectiveType,
+    },
+    AddValue {
+        directive: CspDirectiveType,
+        value: CspValue,
+    },
+    RemoveValue {
+        directive: CspDirectiveType,
+        value: CspValue,
+    },
+    /// Set an unknown directive's raw value, see [CspHeaderBuilder::add_unknown]
+    SetUnknown {
+        name: String,
+        value: String,
+    },
+    RemoveUnknown {
+        name: String,
+    },
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The smallest set of operations turning one policy into another, from
+/// [CspHeaderBuilder::patch_from]. Unlike a [CspDiff] it can be applied, eg. by config management
+/// tools, or shown for review.
+pub struct CspPatch {
+    /// Sorted by directive then value, then unknown directives by name
+    pub operations: Vec<CspPatchOp>,
+}
+
+impl CspPatch {
+    /// Does the patch change nothing?
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Applies the operations in order
+    pub fn apply(&self, mut builder: CspHeaderBuilder) -> CspHeaderBuilder {
+        for op in &self.operations {
+            match op {
+                CspPatchOp::AddDirective { directive, values } => {
+                    builder.directive_map.insert(*directive, values.to_owned());
+                }
+                CspPatchOp::RemoveDirective { directive } => {
+                    builder.directive_map.remove(directive);
+                }
+                CspPatchOp::AddValue { directive, value } => {
+                    let values = builder.directive_map.entry(*directive).or_default();
+                    if !values.contains(value) {
+                        values.push(value.to_owned());
+                    }
+                }
+                CspPatchOp::RemoveValue { directive, value } => {
+                    if let Some(values) = builder.directive_map.get_mut(directive) {
+                        values.retain(|v| v != value);
+                    }
+                }
+                CspPatchOp::SetUnknown { name, value } => {
+                    builder
+                        .unknown_directives
+                        .insert(name.to_owned(), value.to_owned());
+                }
+                CspPatchOp::RemoveUnknown { name } => {
+                    builder.unknown_directives.remove(name);
+                }
+            }
+        }
+        builder
+    }
+}
+
+impl CspHeaderBuilder {
+    /// The minimal [CspPatch] turning `old` into `new`: whole directives are added or removed
+    /// where that's all that changed, otherwise individual values are.
+    pub fn patch_from(old: &Self, new: &Self) -> CspPatch {
+        let diff = old.diff(new);
+        let mut operations = vec![];
+
+        let mut directives: Vec<&CspDirectiveType> = old
+            .directive_map
+            .keys()
+            .chain(new.directive_map.keys())
+            .collect();
+        directives.sort();
+        directives.dedup();
+        for directive in directives {
+            if diff.removed_directives.contains(directive) {
+                operations.push(CspPatchOp::RemoveDirective {
+                    directive: *directive,
+                });
+            } else if diff.added_directives.contains(directive) {
+                let mut values = new.directive_map[directive].to_owned();
+                values.sort();
+                values.dedup();
+                operations.push(CspPatchOp::AddDirective {
+                    directive: *directive,
+                    values,
+                });
+            } else {
+                operations.extend(
+                    diff.removed_values
+                        .iter()
+                        .filter(|(d, _)| d == directive)
+                        .map(|(directive, value)| CspPatchOp::RemoveValue {
+                            directive: *directive,
+                            value: value.to_owned(),
+                        }),
+                );
+                operations.extend(
+                    diff.added_values
+                        .iter()
+                        .filter(|(d, _)| d == directive)
+                        .map(|(directive, value)| CspPatchOp::AddValue {
+                            directive: *directive,
+                            value: value.to_owned(),
+                        }),
+                );
+            }
+        }
+
+        let mut names: Vec<&String> = old
+            .unknown_directives
+            .keys()
+            .chain(new.unknown_directives.keys())
+            .collect();
+        names.sort();
+        names.dedup();
+        for name in names {
+            match (
+                old.unknown_directives.get(name),
+                new.unknown_directives.get(name),
+            ) {
+                (Some(_), None) => operations.push(CspPatchOp::RemoveUnknown {
+                    name: name.to_owned(),
+                }),
+                (before, Some(after)) if before != Some(after) => {
+                    operations.push(CspPatchOp::SetUnknown {
+                        name: name.to_owned(),
+                        value: after.to_owned(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        CspPatch { operations }
+    }
+
+    /// What would change going from this policy to `other`
+    pub fn diff(&self, other: &CspHeaderBuilder) -> CspDiff {
+        let mut directives: Vec<&CspDirectiveType> = self
+            .directive_map
+            .keys()
+            .chain(other.directive_map.keys())
+            .collect();
+        directives.sort();
+        directives.dedup();
+
+        let mut res = CspDiff::default();
+        let empty = vec![];
+        for directive in directives {
+            let before = self.directive_map.get(directive);
+            let after = other.directive_map.get(directive);
+            match (before, after) {
+                (None, Some(_)) => res.added_directives.push(*directive),
+                (Some(_), None) => res.removed_directives.push(*directive),
+                _ => {}
+            }
+            let before = before.unwrap_or(&empty);
+            let after = after.unwrap_or(&empty);
+
+            let mut added: Vec<&CspValue> = after.iter().filter(|v| !before.contains(v)).collect();
+            added.sort();
+            added.dedup();
+            res.added_values
+                .extend(added.into_iter().map(|v| (*directive, v.to_owned())));
+
+            let mut removed: Vec<&CspValue> =
+                before.iter().filter(|v| !after.contains(v)).collect();
+            removed.sort();
+            removed.dedup();
+            res.removed_values
+                .extend(removed.into_iter().map(|v| (*directive, v.to_owned())));
+        }
+
+        // unknown directives' values are compared like PartialEq does, ignoring spacing and order
+        fn normalized(value: &str) -> Vec<&str> {
+            let mut values = value.split_ascii_whitespace().collect::<Vec<&str>>();
+            values.sort();
+            values
+        }
+        let mut names: Vec<&String> = self
+            .unknown_directives
+            .keys()
+            .chain(other.unknown_directives.keys())
+            .collect();
+        names.sort();
+        names.dedup();
+        for name in names {
+            let before = self.unknown_directives.get(name);
+            let after = other.unknown_directives.get(name);
+            if before.map(|v| normalized(v)) == after.map(|v| normalized(v)) {
+                continue;
+            }
+            if let Some(before) = before {
+                res.removed_unknown_directives
+                    .push((name.to_owned(), before.to_owned()));
+            }
+            if let Some(after) = after {
+                res.added_unknown_directives
+                    .push((name.to_owned(), after.to_owned()));
+            }
+        }
+        res
+    }
+
+    /// For contract tests, checks `other` is the same policy (see the `PartialEq` impl), returning
+    /// what would change going from this policy to `other` if it isn't.
+    ///
+    /// ```ignore
+    /// gateway_policy.assert_eq_policy(&backend_policy).expect("policies disagree");
+    /// ```
+    // only called from tests, where the size of the error doesn't matter
+    #[allow(clippy::result_large_err)]
+    pub fn assert_eq_policy(&self, other: &CspHeaderBuilder) -> Result<(), CspDiff> {
+        // compare the live policies, so the diff covers conditional directives too
+        let (before, after) = (self.resolve_conditions(), other.resolve_conditions());
+        if before == after {
+            Ok(())
+        } else {
+            Err(before.diff(&after))
+        }
+    }
+
+    /// What [CspHeaderBuilder::add] would change, without changing anything
+    pub fn preview_add(&self, directive: CspDirectiveType, values: Vec<CspValue>) -> CspDiff {
+        self.diff(&self.clone().add(directive, values))
+    }
+}
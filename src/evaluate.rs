@@ -0,0 +1,189 @@
+//! Checking which resource loads a policy would allow, following the CSP3 matching rules
+
+use crate::{CspDirectiveType, CspHeaderBuilder, CspValue};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A resource load the policy would block, from [CspHeaderBuilder::check_loads]
+pub struct BlockedLoad {
+    pub directive: CspDirectiveType,
+    pub url: String,
+    pub reason: String,
+}
+
+/// The parts of a URL source matching cares about
+struct Url<'a> {
+    scheme: String,
+    host: String,
+    port: Option<u16>,
+    path: &'a str,
+}
+
+impl<'a> Url<'a> {
+    fn parse(url: &'a str) -> Option<Self> {
+        let (scheme, rest) = url.split_once(':')?;
+        let scheme = scheme.to_ascii_lowercase();
+        let Some(rest) = rest.strip_prefix("//") else {
+            // data:, blob: and friends don't have a host
+            return Some(Self {
+                scheme,
+                host: String::new(),
+                port: None,
+                path: rest,
+            });
+        };
+        let (authority, path) = match rest.find(['/', '?', '#']) {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+        let authority = authority.rsplit('@').next().unwrap_or(authority);
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse().ok()?)),
+            None => (authority, None),
+        };
+        Some(Self {
+            scheme,
+            host: host.to_ascii_lowercase(),
+            port,
+            path: path.split(['?', '#']).next().unwrap_or(path),
+        })
+    }
+
+    /// The port, falling back to the scheme's default
+    fn effective_port(&self) -> Option<u16> {
+        self.port.or_else(|| default_port(&self.scheme))
+    }
+}
+
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        _ => None,
+    }
+}
+
+/// Does a source's scheme allow a URL's scheme? Insecure schemes allow their secure upgrades.
+fn scheme_matches(source: &str, url: &str) -> bool {
+    source == url
+        || matches!(
+            (source, url),
+            ("http", "https") | ("ws", "wss" | "http" | "https") | ("wss", "https")
+        )
+}
+
+/// Does a host source like `https://*.example.com:8443/static/` allow the URL?
+fn host_source_matches(source: &str, url: &Url, origin: Option<&Url>) -> bool {
+    let (scheme, rest) = match source.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme.to_ascii_lowercase()), rest),
+        None => (None, source),
+    };
+    let scheme_ok = match &scheme {
+        Some(scheme) => scheme_matches(scheme, &url.scheme),
+        None => match origin {
+            Some(origin) => scheme_matches(&origin.scheme, &url.scheme),
+            None => matches!(url.scheme.as_str(), "http" | "https"),
+        },
+    };
+    if !scheme_ok || url.host.is_empty() {
+        return false;
+    }
+
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host.to_ascii_lowercase(), Some(port)),
+        None => (authority.to_ascii_lowercase(), None),
+    };
+    let host_ok = match host.strip_prefix("*.") {
+        Some(suffix) => url.host.ends_with(&format!(".{suffix}")),
+        None => host == url.host,
+    };
+    let port_ok = match port {
+        Some("*") => true,
+        Some(port) => port.parse::<u16>().ok() == url.effective_port(),
+        None => url.effective_port() == default_port(&url.scheme),
+    };
+    let path_ok = if path.is_empty() {
+        true
+    } else if path.ends_with('/') {
+        url.path.starts_with(path)
+    } else {
+        url.path == path
+    };
+    host_ok && port_ok && path_ok
+}
+
+/// Does a single source value allow the URL?
+fn value_matches(value: &CspValue, url: &Url, origin: Option<&Url>) -> bool {
+    match value {
+        CspValue::SelfSite => origin.is_some_and(|origin| {
+            origin.host == url.host
+                && ((origin.scheme == url.scheme && origin.effective_port() == url.effective_port())
+                    // same-origin resources may be upgraded to https
+                    || (origin.scheme == "http"
+                        && matches!(url.scheme.as_str(), "https" | "wss")
+                        && url.effective_port() == default_port(&url.scheme)))
+        }),
+        // `*` doesn't match schemes like data: and blob:, only network ones and the page's own
+        CspValue::Host { value } if value == "*" => {
+            matches!(url.scheme.as_str(), "http" | "https" | "ws" | "wss")
+                || origin.is_some_and(|origin| origin.scheme == url.scheme)
+        }
+        CspValue::Host { value } => host_source_matches(value, url, origin),
+        CspValue::SchemeHttps => scheme_matches("https", &url.scheme),
+        CspValue::SchemeHttp => scheme_matches("http", &url.scheme),
+        CspValue::SchemeData => url.scheme == "data",
+        CspValue::SchemeOther { value } => scheme_matches(
+            &value.trim_end_matches(':').to_ascii_lowercase(),
+            &url.scheme,
+        ),
+        // keywords, nonces and hashes don't allow anything by URL
+        _ => false,
+    }
+}
+
+impl CspHeaderBuilder {
+    /// Checks a list of `(directive, url)` resource loads against the policy, as loaded from a page
+    /// at `origin`, and returns the ones which would be blocked and why.
+    ///
+    /// This follows the CSP3 URL matching rules for hosts, schemes, ports, paths and `'self'`,
+    /// including directive fallbacks. Nonces, hashes and `'strict-dynamic'` don't apply to URLs,
+    /// so they never allow a load here.
+    pub fn check_loads(
+        &self,
+        loads: &[(CspDirectiveType, &str)],
+        origin: &str,
+    ) -> Vec<BlockedLoad> {
+        let origin = Url::parse(origin);
+        loads
+            .iter()
+            .filter_map(|(directive, url)| {
+                let blocked = |reason: String| {
+                    Some(BlockedLoad {
+                        directive: *directive,
+                        url: url.to_string(),
+                        reason,
+                    })
+                };
+                let (effective, values) = self.effective_values(*directive)?;
+                let Some(parsed) = Url::parse(url) else {
+                    return blocked("not a valid absolute URL".to_string());
+                };
+                if values
+                    .iter()
+                    .any(|v| value_matches(v, &parsed, origin.as_ref()))
+                {
+                    None
+                } else if effective == *directive {
+                    blocked(format!("not allowed by {effective}"))
+                } else {
+                    blocked(format!(
+                        "not allowed by {effective}, which {directive} falls back to"
+                    ))
+                }
+            })
+            .collect()
+    }
+}
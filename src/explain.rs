@@ -0,0 +1,80 @@
+//! Describing a policy in plain English, eg. for a dashboard
+
+use crate::{CspDirectiveType, CspHeaderBuilder, CspValue};
+
+/// What a single value allows, in a few words
+fn describe_value(value: &CspValue, origin: Option<&str>) -> String {
+    match value {
+        CspValue::None => "nothing".to_string(),
+        CspValue::SelfSite => match origin {
+            Some(origin) => format!("same origin ({origin})"),
+            None => "same origin".to_string(),
+        },
+        CspValue::StrictDynamic => "scripts loaded by already-trusted scripts".to_string(),
+        CspValue::ReportSample => "code samples in violation reports".to_string(),
+        CspValue::UnsafeInline => "inline code (unsafe)".to_string(),
+        CspValue::UnsafeEval => "eval() and similar (unsafe)".to_string(),
+        CspValue::WasmUnsafeEval => "compiling WebAssembly".to_string(),
+        CspValue::UnsafeHashes => "event handler attributes matching a hash (unsafe)".to_string(),
+        CspValue::UnsafeAllowRedirects => "navigations which redirect".to_string(),
+        CspValue::InlineSpeculationRules => "inline speculation rules".to_string(),
+        CspValue::TrustedTypesEval => "eval() when Trusted Types are enforced".to_string(),
+        CspValue::AllowDuplicates => "policies with duplicate names".to_string(),
+        CspValue::ScriptSink => "Trusted Types for script sinks".to_string(),
+        CspValue::OtherSink { value } => format!("Trusted Types for {value:?} sinks"),
+        CspValue::Host { value } if value == "*" => "any URL".to_string(),
+        CspValue::Host { value } => value.to_owned(),
+        CspValue::SchemeHttps => "any https: URL".to_string(),
+        CspValue::SchemeHttp => "any http: URL".to_string(),
+        CspValue::SchemeData => "data: URLs".to_string(),
+        CspValue::SchemeOther { value } => format!("any {value} URL"),
+        CspValue::Nonce { value } => format!("elements with the nonce {value:?}"),
+        CspValue::NoncePlaceholder => "elements with the per-request nonce".to_string(),
+        CspValue::Sha256 { .. } | CspValue::Sha384 { .. } | CspValue::Sha512 { .. } => {
+            "inline code matching a hash".to_string()
+        }
+    }
+}
+
+impl CspHeaderBuilder {
+    /// Describes the policy, one line per directive in sort order, eg.
+    /// `script-src: same origin (https://example.com), https://cdn.example.com`.
+    ///
+    /// If `origin` is given it's shown wherever `'self'` is used, to make the output concrete.
+    pub fn explain(&self, origin: Option<&str>) -> Vec<String> {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .collect::<Vec<&CspDirectiveType>>();
+        keys.sort();
+        keys.into_iter()
+            .map(|directive| {
+                let mut values = self.directive_map[directive].to_owned();
+                values.sort();
+                values.dedup();
+                let description = if values.is_empty() && directive.takes_source_list() {
+                    // browsers treat an empty source list like 'none'
+                    "nothing (blocked)".to_string()
+                } else if values.is_empty() {
+                    "enabled".to_string()
+                } else if directive.takes_source_list()
+                    || *directive == CspDirectiveType::RequireTrustedTypesFor
+                {
+                    values
+                        .iter()
+                        .map(|v| describe_value(v, origin))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                } else {
+                    // report-to, sandbox and friends hold names rather than sources
+                    values
+                        .into_iter()
+                        .map(String::from)
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                };
+                format!("{directive}: {description}")
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,136 @@
+//! Human-readable descriptions of the values each directive accepts, for UIs and messages
+
+use crate::CspDirectiveType;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// A kind of value a directive accepts, with a short description
+pub struct ValueHint {
+    /// The token as it appears in a policy, with placeholders in `<angle brackets>`
+    pub token: &'static str,
+    pub description: &'static str,
+    /// Not widely supported by browsers yet
+    pub experimental: bool,
+    /// Weakens the protection the directive gives
+    pub dangerous: bool,
+}
+
+const fn hint(token: &'static str, description: &'static str) -> ValueHint {
+    ValueHint {
+        token,
+        description,
+        experimental: false,
+        dangerous: false,
+    }
+}
+
+const fn dangerous(token: &'static str, description: &'static str) -> ValueHint {
+    ValueHint {
+        dangerous: true,
+        ..hint(token, description)
+    }
+}
+
+/// The hint for one of the keywords from [CspDirectiveType::allowed_keywords]
+fn keyword_hint(keyword: &'static str) -> ValueHint {
+    match keyword {
+        "'strict-dynamic'" => hint(
+            keyword,
+            "scripts loaded by a trusted script are trusted too, and host allowlists are ignored",
+        ),
+        "'report-sample'" => hint(
+            keyword,
+            "include the start of the offending code in violation reports",
+        ),
+        "'unsafe-inline'" => dangerous(keyword, "allow inline code and event handlers"),
+        "'unsafe-eval'" => dangerous(keyword, "allow eval() and similar string-to-code functions"),
+        "'wasm-unsafe-eval'" => dangerous(keyword, "allow compiling WebAssembly"),
+        "'unsafe-hashes'" => dangerous(
+            keyword,
+            "allow event handler attributes which match a listed hash",
+        ),
+        "'unsafe-allow-redirects'" => ValueHint {
+            experimental: true,
+            ..hint(keyword, "allow navigations which redirect elsewhere")
+        },
+        "'inline-speculation-rules'" => ValueHint {
+            experimental: true,
+            ..hint(
+                keyword,
+                "allow inline speculation rules for prefetching and prerendering",
+            )
+        },
+        "'trusted-types-eval'" => ValueHint {
+            experimental: true,
+            ..dangerous(keyword, "allow eval() when Trusted Types are enforced")
+        },
+        _ => hint(keyword, ""),
+    }
+}
+
+impl CspDirectiveType {
+    /// The kinds of value this directive accepts, with descriptions, eg. for tooltips in an admin
+    /// UI. Directives which take a source list share most of their hints.
+    pub fn value_hints(&self) -> Vec<ValueHint> {
+        if !self.takes_source_list() {
+            return match self {
+                CspDirectiveType::RequireTrustedTypesFor => vec![hint(
+                    "'script'",
+                    "require Trusted Types for script injection sinks",
+                )],
+                CspDirectiveType::ReportTo => vec![hint(
+                    "<group>",
+                    "the Reporting-Endpoints group to send violation reports to",
+                )],
+                CspDirectiveType::ReportUri => {
+                    vec![hint("<url>", "where to POST violation reports")]
+                }
+                CspDirectiveType::Sandbox => vec![hint(
+                    "<allow-token>",
+                    "a sandbox restriction to lift, eg. allow-scripts",
+                )],
+                CspDirectiveType::TrustedTypes => vec![
+                    hint(
+                        "<policy-name>",
+                        "a Trusted Types policy which may be created",
+                    ),
+                    hint("'none'", "no Trusted Types policies may be created"),
+                    dangerous(
+                        "'allow-duplicates'",
+                        "allow creating several policies with the same name",
+                    ),
+                ],
+                _ => vec![],
+            };
+        }
+
+        let mut hints = vec![
+            hint("'none'", "nothing, can't be combined with other values"),
+            hint("'self'", "same-origin resources"),
+        ];
+        let keywords = self.allowed_keywords();
+        hints.extend(keywords.iter().map(|k| keyword_hint(k)));
+        hints.push(hint(
+            "<host>",
+            "resources from a host, eg. https://example.com or *.example.com",
+        ));
+        hints.push(hint(
+            "<scheme>:",
+            "resources using a scheme, eg. https: or data:",
+        ));
+        hints.push(dangerous(
+            "*",
+            "any URL except data:, blob: and filesystem:",
+        ));
+        if keywords.contains(&"'unsafe-inline'") {
+            hints.push(hint(
+                "'nonce-<base64>'",
+                "inline code carrying a matching nonce attribute",
+            ));
+            hints.push(hint(
+                "'sha256-<base64>'",
+                "inline code matching a hash, sha384 and sha512 work too",
+            ));
+        }
+        hints
+    }
+}
@@ -3,12 +3,69 @@
 
 use axum::http::HeaderValue;
 use regex::RegexSet;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
+
+mod accumulator;
+mod analysis;
+mod annotate;
+mod browsers;
+mod compat;
+mod conditional;
+mod diff;
+mod evaluate;
+mod explain;
+mod hints;
+mod macros;
+mod merge;
+mod meta;
+pub mod middleware;
+mod nonce;
+mod parse;
+mod presets;
+mod render;
+mod reporting;
+mod routes;
+mod sandbox;
+mod schema;
+mod services;
+mod validate;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+pub use accumulator::CspPolicyAccumulator;
+pub use annotate::CspAnnotation;
+pub use browsers::{Browser, BrowserVersion};
+#[allow(deprecated)]
+pub use compat::CspSetBuilder;
+pub use conditional::CspConditional;
+pub use diff::{CspDiff, CspPatch, CspPatchOp};
+pub use evaluate::BlockedLoad;
+pub use hints::ValueHint;
+#[doc(hidden)]
+pub use macros::__check_policy;
+pub use merge::{AllowedValues, MergePolicy, Rejection};
+pub use nonce::{
+    Nonce, NonceConfig, NonceContext, NonceEncoding, NonceLengthError, NonceSource,
+    RandomNonceSource, MIN_NONCE_BYTES,
+};
+pub use parse::{ConfigError, ParseError};
+pub use render::{FinishError, RenderError};
+pub use reporting::{ReportingConfig, REPORTING_ENDPOINTS};
+pub use routes::GlobError;
+pub use sandbox::SandboxToken;
+pub use schema::{CspSchema, SchemaViolation};
+pub use services::KnownService;
+pub use validate::{CspValidator, CspWarning, Severity, ValidationOptions};
 
 #[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CspDirectiveType {
     BaseUri,
+    /// Deprecated, use [CspDirectiveType::UpgradeInsecureRequests] instead
+    BlockAllMixedContent,
     ChildSrc,
     ConnectSrc,
     DefaultSrc,
@@ -29,6 +86,8 @@ pub enum CspDirectiveType {
     ReportTo,
     // Experimental/Deprecated, you should use this AND report-to
     ReportUri,
+    /// Deprecated and non-standard, browsers no longer support it
+    RequireSriFor,
     // Experimental!
     RequireTrustedTypesFor,
     Sandbox,
@@ -44,10 +103,154 @@ pub enum CspDirectiveType {
     WorkerSource,
 }
 
-impl AsRef<str> for CspDirectiveType {
-    fn as_ref(&self) -> &str {
+impl CspDirectiveType {
+    /// Every directive type, in sort order
+    pub const ALL: &'static [CspDirectiveType] = &[
+        CspDirectiveType::BaseUri,
+        CspDirectiveType::BlockAllMixedContent,
+        CspDirectiveType::ChildSrc,
+        CspDirectiveType::ConnectSrc,
+        CspDirectiveType::DefaultSrc,
+        CspDirectiveType::FencedFrameSrc,
+        CspDirectiveType::FontSrc,
+        CspDirectiveType::FormAction,
+        CspDirectiveType::FrameAncestors,
+        CspDirectiveType::FrameSrc,
+        CspDirectiveType::ImgSrc,
+        CspDirectiveType::ManifestSrc,
+        CspDirectiveType::MediaSrc,
+        CspDirectiveType::NavigateTo,
+        CspDirectiveType::ObjectSrc,
+        CspDirectiveType::PrefetchSrc,
+        CspDirectiveType::ReportTo,
+        CspDirectiveType::ReportUri,
+        CspDirectiveType::RequireSriFor,
+        CspDirectiveType::RequireTrustedTypesFor,
+        CspDirectiveType::Sandbox,
+        CspDirectiveType::ScriptSource,
+        CspDirectiveType::ScriptSourceAttr,
+        CspDirectiveType::ScriptSourceElem,
+        CspDirectiveType::StyleSource,
+        CspDirectiveType::StyleSourceAttr,
+        CspDirectiveType::StyleSourceElem,
+        CspDirectiveType::TrustedTypes,
+        CspDirectiveType::UpgradeInsecureRequests,
+        CspDirectiveType::WorkerSource,
+    ];
+
+    /// Is this directive experimental, ie. not widely supported by browsers yet?
+    pub fn is_experimental(&self) -> bool {
+        matches!(
+            self,
+            CspDirectiveType::FencedFrameSrc
+                | CspDirectiveType::NavigateTo
+                | CspDirectiveType::ReportTo
+                | CspDirectiveType::RequireTrustedTypesFor
+                | CspDirectiveType::TrustedTypes
+        )
+    }
+
+    /// The quoted keywords which mean something in this directive, beyond `'self'` and `'none'`
+    /// which are valid in any directive taking a source list.
+    pub const fn allowed_keywords(&self) -> &'static [&'static str] {
+        match self {
+            CspDirectiveType::DefaultSrc
+            | CspDirectiveType::ScriptSource
+            | CspDirectiveType::ScriptSourceElem => &[
+                "'strict-dynamic'",
+                "'report-sample'",
+                "'unsafe-inline'",
+                "'unsafe-eval'",
+                "'wasm-unsafe-eval'",
+                "'unsafe-hashes'",
+                "'inline-speculation-rules'",
+                "'trusted-types-eval'",
+            ],
+            CspDirectiveType::ScriptSourceAttr
+            | CspDirectiveType::StyleSource
+            | CspDirectiveType::StyleSourceAttr => {
+                &["'report-sample'", "'unsafe-inline'", "'unsafe-hashes'"]
+            }
+            CspDirectiveType::StyleSourceElem => &["'report-sample'", "'unsafe-inline'"],
+            CspDirectiveType::NavigateTo => &["'unsafe-allow-redirects'"],
+            _ => &[],
+        }
+    }
+
+    /// Does this directive take a source list (`'self'`, hosts, schemes...) as its value?
+    pub const fn takes_source_list(&self) -> bool {
+        !matches!(
+            self,
+            CspDirectiveType::BlockAllMixedContent
+                | CspDirectiveType::ReportTo
+                | CspDirectiveType::ReportUri
+                | CspDirectiveType::RequireSriFor
+                | CspDirectiveType::RequireTrustedTypesFor
+                | CspDirectiveType::Sandbox
+                | CspDirectiveType::TrustedTypes
+                | CspDirectiveType::UpgradeInsecureRequests
+        )
+    }
+
+    /// Has this directive been deprecated or removed from the spec?
+    pub fn is_deprecated(&self) -> bool {
+        matches!(
+            self,
+            CspDirectiveType::BlockAllMixedContent
+                | CspDirectiveType::NavigateTo
+                | CspDirectiveType::PrefetchSrc
+                | CspDirectiveType::ReportUri
+                | CspDirectiveType::RequireSriFor
+        )
+    }
+
+    /// For deprecated directives, the directive which does the same job in modern browsers, if
+    /// there's a direct replacement
+    pub fn modern_replacement(&self) -> Option<CspDirectiveType> {
+        match self {
+            CspDirectiveType::BlockAllMixedContent => {
+                Some(CspDirectiveType::UpgradeInsecureRequests)
+            }
+            CspDirectiveType::ReportUri => Some(CspDirectiveType::ReportTo),
+            _ => None,
+        }
+    }
+
+    /// The directive browsers use instead when this one isn't set, per the
+    /// [CSP3 fallback list](https://www.w3.org/TR/CSP3/#directive-fallback-list).
+    pub fn fallback(&self) -> Option<CspDirectiveType> {
+        match self {
+            CspDirectiveType::ScriptSourceElem | CspDirectiveType::ScriptSourceAttr => {
+                Some(CspDirectiveType::ScriptSource)
+            }
+            CspDirectiveType::StyleSourceElem | CspDirectiveType::StyleSourceAttr => {
+                Some(CspDirectiveType::StyleSource)
+            }
+            // worker-src then tries script-src, see CspHeaderBuilder::effective_values
+            CspDirectiveType::WorkerSource => Some(CspDirectiveType::ChildSrc),
+            CspDirectiveType::FencedFrameSrc => Some(CspDirectiveType::FrameSrc),
+            CspDirectiveType::FrameSrc => Some(CspDirectiveType::ChildSrc),
+            CspDirectiveType::ChildSrc
+            | CspDirectiveType::ConnectSrc
+            | CspDirectiveType::FontSrc
+            | CspDirectiveType::ImgSrc
+            | CspDirectiveType::ManifestSrc
+            | CspDirectiveType::MediaSrc
+            | CspDirectiveType::ObjectSrc
+            | CspDirectiveType::PrefetchSrc
+            | CspDirectiveType::ScriptSource
+            | CspDirectiveType::StyleSource => Some(CspDirectiveType::DefaultSrc),
+            _ => None,
+        }
+    }
+}
+
+impl CspDirectiveType {
+    /// The directive's name, as [AsRef] but usable in `const` contexts
+    pub const fn name(&self) -> &'static str {
         match self {
             CspDirectiveType::BaseUri => "base-uri",
+            CspDirectiveType::BlockAllMixedContent => "block-all-mixed-content",
             CspDirectiveType::ChildSrc => "child-src",
             CspDirectiveType::ConnectSrc => "connect-src",
             CspDirectiveType::DefaultSrc => "default-src",
@@ -68,6 +271,7 @@ impl AsRef<str> for CspDirectiveType {
             CspDirectiveType::ReportTo => "report-to",
             // Experimental/Deprecated, you should use this AND report-to
             CspDirectiveType::ReportUri => "report-uri",
+            CspDirectiveType::RequireSriFor => "require-sri-for",
             // Experimental!
             CspDirectiveType::RequireTrustedTypesFor => "require-trusted-types-for",
             CspDirectiveType::Sandbox => "sandbox",
@@ -85,6 +289,12 @@ impl AsRef<str> for CspDirectiveType {
     }
 }
 
+impl AsRef<str> for CspDirectiveType {
+    fn as_ref(&self) -> &str {
+        self.name()
+    }
+}
+
 impl Display for CspDirectiveType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_ref())
@@ -98,6 +308,7 @@ impl From<CspDirectiveType> for String {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CspDirective {
     pub directive_type: CspDirectiveType,
     pub values: Vec<CspValue>,
@@ -194,6 +405,32 @@ impl CspUrlMatcher {
     }
 }
 
+impl CspUrlMatcher {
+    /// How many different header values the matchers produce
+    pub fn distinct_policies(matchers: &[CspUrlMatcher]) -> usize {
+        let mut headers: Vec<HeaderValue> =
+            matchers.iter().cloned().map(HeaderValue::from).collect();
+        headers.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        headers.dedup();
+        headers.len()
+    }
+
+    /// The header value for each matcher, in the same order, where matchers producing the same
+    /// policy share one `Arc`. Responses can then reuse identical header bytes.
+    pub fn shared_headers(matchers: &[CspUrlMatcher]) -> Vec<Arc<HeaderValue>> {
+        let mut seen: HashMap<HeaderValue, Arc<HeaderValue>> = HashMap::new();
+        matchers
+            .iter()
+            .map(|matcher| {
+                let header = HeaderValue::from(matcher.clone());
+                seen.entry(header.clone())
+                    .or_insert_with(|| Arc::new(header))
+                    .clone()
+            })
+            .collect()
+    }
+}
+
 /// Returns the statement as it should show up in the headers
 impl From<CspUrlMatcher> for HeaderValue {
     fn from(input: CspUrlMatcher) -> HeaderValue {
@@ -209,7 +446,8 @@ impl From<CspUrlMatcher> for HeaderValue {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Enum for [CSP source values](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Security-Policy/Sources#sources)
 pub enum CspValue {
     None,
@@ -224,6 +462,19 @@ pub enum CspValue {
     UnsafeHashes,
     /// Experimental!
     UnsafeAllowRedirects,
+    /// Experimental! Allows inline `<script type="speculationrules">`
+    InlineSpeculationRules,
+    /// Experimental! Allows `eval()` when Trusted Types are enforced
+    TrustedTypesEval,
+    /// `'allow-duplicates'`, for `trusted-types`
+    AllowDuplicates,
+    /// The `'script'` sink group, for `require-trusted-types-for`
+    ScriptSink,
+    /// A `require-trusted-types-for` sink group this crate doesn't know about yet, kept for
+    /// forwards compatibility. Renders as `'{value}'`.
+    OtherSink {
+        value: String,
+    },
     Host {
         value: String,
     },
@@ -236,6 +487,10 @@ pub enum CspValue {
     Nonce {
         value: String,
     },
+    /// Stands in for a nonce which is only known later, see [CspHeaderBuilder::finish_with_context].
+    /// If it's never resolved it renders as `'none'`, which blocks everything on its own and is
+    /// ignored alongside other values, so a missing nonce fails closed.
+    NoncePlaceholder,
     Sha256 {
         value: String,
     },
@@ -247,6 +502,23 @@ pub enum CspValue {
     },
 }
 
+/// Values are ordered canonically: keywords, hosts, schemes (`https:`, `http:`, `data:` then any
+/// others), nonces, then hashes, and alphabetically within a kind. This is the order values
+/// render in.
+impl Ord for CspValue {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.sort_rank()
+            .cmp(&other.sort_rank())
+            .then_with(|| self.inner_value().cmp(&other.inner_value()))
+    }
+}
+
+impl PartialOrd for CspValue {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl From<CspValue> for String {
     fn from(input: CspValue) -> String {
         match input {
@@ -259,35 +531,232 @@ impl From<CspValue> for String {
             CspValue::WasmUnsafeEval => "'wasm-unsafe-eval'".to_string(),
             CspValue::UnsafeHashes => "'unsafe-hashes'".to_string(),
             CspValue::UnsafeAllowRedirects => "'unsafe-allow-redirects'".to_string(),
+            CspValue::InlineSpeculationRules => "'inline-speculation-rules'".to_string(),
+            CspValue::TrustedTypesEval => "'trusted-types-eval'".to_string(),
+            CspValue::AllowDuplicates => "'allow-duplicates'".to_string(),
+            CspValue::ScriptSink => "'script'".to_string(),
+            CspValue::OtherSink { value } => format!("'{value}'"),
             CspValue::SchemeHttps => "https:".to_string(),
             CspValue::SchemeHttp => "http:".to_string(),
             CspValue::SchemeData => "data:".to_string(),
             CspValue::Host { value } | CspValue::SchemeOther { value } => value.to_string(),
-            CspValue::Nonce { value } => format!("nonce-{value}"),
-            CspValue::Sha256 { value } => format!("sha256-{value}"),
-            CspValue::Sha384 { value } => format!("sha384-{value}"),
-            CspValue::Sha512 { value } => format!("sha512-{value}"),
+            CspValue::Nonce { value } => format!("'nonce-{value}'"),
+            CspValue::NoncePlaceholder => "'none'".to_string(),
+            CspValue::Sha256 { value } => format!("'sha256-{value}'"),
+            CspValue::Sha384 { value } => format!("'sha384-{value}'"),
+            CspValue::Sha512 { value } => format!("'sha512-{value}'"),
+        }
+    }
+}
+
+impl CspValue {
+    /// Where this sorts when rendering, see the `Ord` impl
+    fn sort_rank(&self) -> u8 {
+        match self {
+            CspValue::None => 0,
+            CspValue::SelfSite => 1,
+            CspValue::StrictDynamic => 2,
+            CspValue::ReportSample => 3,
+            CspValue::UnsafeInline => 4,
+            CspValue::UnsafeEval => 5,
+            CspValue::WasmUnsafeEval => 6,
+            CspValue::UnsafeHashes => 7,
+            CspValue::UnsafeAllowRedirects => 8,
+            CspValue::InlineSpeculationRules => 9,
+            CspValue::TrustedTypesEval => 10,
+            CspValue::AllowDuplicates => 11,
+            CspValue::ScriptSink => 15,
+            CspValue::OtherSink { .. } => 16,
+            CspValue::Host { .. } => 20,
+            CspValue::SchemeHttps => 30,
+            CspValue::SchemeHttp => 31,
+            CspValue::SchemeData => 32,
+            CspValue::SchemeOther { .. } => 33,
+            CspValue::Nonce { .. } => 40,
+            CspValue::NoncePlaceholder => 41,
+            CspValue::Sha256 { .. } => 50,
+            CspValue::Sha384 { .. } => 51,
+            CspValue::Sha512 { .. } => 52,
+        }
+    }
+
+    /// The string part of the variants which carry one
+    fn inner_value(&self) -> Option<&str> {
+        match self {
+            CspValue::OtherSink { value }
+            | CspValue::Host { value }
+            | CspValue::SchemeOther { value }
+            | CspValue::Nonce { value }
+            | CspValue::Sha256 { value }
+            | CspValue::Sha384 { value }
+            | CspValue::Sha512 { value } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Is this value experimental, ie. not widely supported by browsers yet?
+    pub fn is_experimental(&self) -> bool {
+        matches!(
+            self,
+            CspValue::UnsafeAllowRedirects
+                | CspValue::InlineSpeculationRules
+                | CspValue::TrustedTypesEval
+        )
+    }
+
+    /// The keyword, for quoted keywords which are only meaningful in some directives
+    pub(crate) fn restricted_keyword(&self) -> Option<&'static str> {
+        match self {
+            CspValue::StrictDynamic => Some("'strict-dynamic'"),
+            CspValue::ReportSample => Some("'report-sample'"),
+            CspValue::UnsafeInline => Some("'unsafe-inline'"),
+            CspValue::UnsafeEval => Some("'unsafe-eval'"),
+            CspValue::WasmUnsafeEval => Some("'wasm-unsafe-eval'"),
+            CspValue::UnsafeHashes => Some("'unsafe-hashes'"),
+            CspValue::UnsafeAllowRedirects => Some("'unsafe-allow-redirects'"),
+            CspValue::InlineSpeculationRules => Some("'inline-speculation-rules'"),
+            CspValue::TrustedTypesEval => Some("'trusted-types-eval'"),
+            _ => None,
+        }
+    }
+
+    /// Is this the `*` wildcard host source?
+    pub(crate) fn is_wildcard(&self) -> bool {
+        matches!(self, CspValue::Host { value } if value == "*")
+    }
+
+    /// Is this a `'nonce-...'` or hash source?
+    pub(crate) fn is_nonce_or_hash(&self) -> bool {
+        matches!(
+            self,
+            CspValue::Nonce { .. }
+                | CspValue::NoncePlaceholder
+                | CspValue::Sha256 { .. }
+                | CspValue::Sha384 { .. }
+                | CspValue::Sha512 { .. }
+        )
+    }
+
+    /// Is this a host or scheme source, ie. something that allowlists by location?
+    pub(crate) fn is_host_or_scheme(&self) -> bool {
+        matches!(
+            self,
+            CspValue::Host { .. }
+                | CspValue::SchemeHttps
+                | CspValue::SchemeHttp
+                | CspValue::SchemeData
+                | CspValue::SchemeOther { .. }
+        )
+    }
+
+    /// Host and scheme values with any surrounding whitespace (eg. from copy-pasting) removed
+    pub(crate) fn trimmed(self) -> Self {
+        match self {
+            CspValue::Host { value } => CspValue::Host {
+                value: value.trim().to_string(),
+            },
+            CspValue::SchemeOther { value } => CspValue::SchemeOther {
+                value: value.trim().to_string(),
+            },
+            value => value,
         }
     }
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Builder that ends up in a HeaderValue
+///
+/// The rendered policy is canonical: directives and values are always sorted and de-duplicated, so
+/// the same policy renders to the same bytes regardless of the order things were added or
+/// `HashMap` iteration order. This is a stability guarantee, so it's safe to snapshot-test output.
+///
+/// With the `serde` feature, builders round-trip through human-readable formats like JSON and
+/// compact binary ones like `postcard` or `bincode`, eg. for caching a policy in Redis. Nothing
+/// relies on a self-describing format, so binary formats restore the builder exactly.
 pub struct CspHeaderBuilder {
     pub directive_map: HashMap<CspDirectiveType, Vec<CspValue>>,
+    /// Documentation attached to the policy, which is never rendered into the header.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub annotations: Vec<CspAnnotation>,
+    /// Directives this crate doesn't know about yet, by lowercase name, holding their raw
+    /// values. They're passed through to the header, see [CspHeaderBuilder::add_unknown].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unknown_directives: HashMap<String, String>,
+    /// Directives which are only included while their condition holds, see
+    /// [CspHeaderBuilder::add_conditional].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub conditional_directives: Vec<CspConditional>,
+    /// Problems with values passed to [CspHeaderBuilder::add] which it fixed up, like hosts with
+    /// surrounding whitespace, reported by [CspHeaderBuilder::validate].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub input_warnings: Vec<CspWarning>,
 }
 
+/// Builders are equal when they render the same policy, so the order values were added in,
+/// duplicates, annotations, and the spacing and order of values in unknown directives don't
+/// matter.
+impl PartialEq for CspHeaderBuilder {
+    fn eq(&self, other: &Self) -> bool {
+        self.render(&[], true) == other.render(&[], true)
+    }
+}
+
+impl Eq for CspHeaderBuilder {}
+
 impl CspHeaderBuilder {
     pub fn new() -> Self {
         Self {
             directive_map: HashMap::new(),
+            annotations: vec![],
+            unknown_directives: HashMap::new(),
+            conditional_directives: vec![],
+            input_warnings: vec![],
         }
     }
 
+    /// Passes through a directive this crate doesn't know about, eg. one newer than it, with its
+    /// raw value. As with duplicate directives in browsers, the first one added wins.
+    pub fn add_unknown(mut self, name: &str, value: &str) -> Self {
+        self.unknown_directives
+            .entry(name.trim().to_ascii_lowercase())
+            .or_insert_with(|| value.to_string());
+        self
+    }
+
+    /// Adds values to a directive, skipping any it already has.
+    ///
+    /// Surrounding whitespace is trimmed from host and scheme values, so `" https://example.com "`
+    /// doesn't render as stray tokens, and ones which are empty after trimming are dropped. Both
+    /// are usually copy-paste mistakes, so they're reported by [CspHeaderBuilder::validate].
+    /// Whitespace inside a value is still an error, which [CspDirective::render_checked] reports.
     pub fn add(mut self, directive: CspDirectiveType, values: Vec<CspValue>) -> Self {
         self.directive_map.entry(directive).or_default();
 
         values.into_iter().for_each(|val| {
+            let original = match &val {
+                CspValue::Host { value } | CspValue::SchemeOther { value } => Some(value.clone()),
+                _ => None,
+            };
+            let val = val.trimmed();
+            if let Some(original) = original {
+                let trimmed = String::from(val.clone());
+                if trimmed.is_empty() {
+                    self.input_warnings.push(CspWarning::new(
+                        Severity::Warning,
+                        Some(directive),
+                        format!("dropped the empty value {original:?}"),
+                    ));
+                    return;
+                }
+                if trimmed != original {
+                    self.input_warnings.push(CspWarning::new(
+                        Severity::Warning,
+                        Some(directive),
+                        format!("trimmed whitespace from {original:?}"),
+                    ));
+                }
+            }
             if !self.directive_map.get(&directive).unwrap().contains(&val) {
                 self.directive_map.get_mut(&directive).unwrap().push(val);
             }
@@ -295,12 +764,211 @@ impl CspHeaderBuilder {
         self
     }
 
-    pub fn finish(self) -> HeaderValue {
+    /// Like [CspHeaderBuilder::add], but only if the directive is missing or has no values yet,
+    /// so a default doesn't override explicit configuration.
+    pub fn add_if_absent(self, directive: CspDirectiveType, values: Vec<CspValue>) -> Self {
+        match self.directive_map.get(&directive) {
+            Some(existing) if !existing.is_empty() => self,
+            _ => self.add(directive, values),
+        }
+    }
+
+    /// Makes sure the policy has a `default-src` catch-all, adding one with `fallback` if it's
+    /// missing or empty.
+    pub fn require_default_src(self, fallback: Vec<CspValue>) -> Self {
+        self.add_if_absent(CspDirectiveType::DefaultSrc, fallback)
+    }
+
+    /// Renames a reporting group in the `report-to` directive, so it stays in sync with the
+    /// group name used in the `Reporting-Endpoints` header.
+    pub fn rename_report_group(mut self, from: &str, to: &str) -> Self {
+        if let Some(values) = self.directive_map.get_mut(&CspDirectiveType::ReportTo) {
+            let mut renamed: Vec<CspValue> = vec![];
+            for val in values.drain(..) {
+                let val = match val {
+                    CspValue::Host { value } if value == from => CspValue::Host {
+                        value: to.to_string(),
+                    },
+                    CspValue::SchemeOther { value } if value == from => CspValue::SchemeOther {
+                        value: to.to_string(),
+                    },
+                    val => val,
+                };
+                if !renamed.contains(&val) {
+                    renamed.push(val);
+                }
+            }
+            *values = renamed;
+        }
+        self
+    }
+
+    /// Runs `f` over the values of every directive, in sort order, eg. to strip a value from the
+    /// whole policy. Directives left empty are kept, since some of them mean something empty.
+    pub fn map_values(mut self, f: impl Fn(CspDirectiveType, &mut Vec<CspValue>)) -> Self {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .copied()
+            .collect::<Vec<CspDirectiveType>>();
+        keys.sort();
+        for directive in keys {
+            if let Some(values) = self.directive_map.get_mut(&directive) {
+                f(directive, values);
+            }
+        }
+        self
+    }
+
+    /// Returns the directive (and its values) that applies for `directive`, following the
+    /// fallback list when it isn't set.
+    pub fn effective_values(
+        &self,
+        directive: CspDirectiveType,
+    ) -> Option<(CspDirectiveType, &Vec<CspValue>)> {
+        let chain = match directive {
+            // worker-src is the odd one out, it tries script-src before default-src
+            CspDirectiveType::WorkerSource => vec![
+                CspDirectiveType::WorkerSource,
+                CspDirectiveType::ChildSrc,
+                CspDirectiveType::ScriptSource,
+                CspDirectiveType::DefaultSrc,
+            ],
+            directive => {
+                let mut chain = vec![directive];
+                while let Some(next) = chain.last().and_then(|d| d.fallback()) {
+                    chain.push(next);
+                }
+                chain
+            }
+        };
+        chain.into_iter().find_map(|directive| {
+            self.directive_map
+                .get(&directive)
+                .map(|values| (directive, values))
+        })
+    }
+
+    /// Drops directives which are redundant because their fallback directive has the same
+    /// values, eg. a `script-src-elem` identical to `script-src`. What the browser enforces is
+    /// unchanged.
+    pub fn minified(&self) -> Self {
+        let canonical = |values: Option<(CspDirectiveType, &Vec<CspValue>)>| {
+            values.map(|(_, values)| {
+                let mut values = values.to_owned();
+                values.sort();
+                values.dedup();
+                values
+            })
+        };
+        let enforced = |builder: &CspHeaderBuilder| {
+            CspDirectiveType::ALL
+                .iter()
+                .map(|d| canonical(builder.effective_values(*d)))
+                .collect::<Vec<_>>()
+        };
+        let original = enforced(self);
+
+        let mut res = self.clone();
         let mut keys = self
             .directive_map
             .keys()
+            .filter(|d| d.fallback().is_some())
             .collect::<Vec<&CspDirectiveType>>();
         keys.sort();
+        for directive in keys {
+            let removed = res.directive_map.remove(directive);
+            if enforced(&res) != original {
+                if let Some(values) = removed {
+                    res.directive_map.insert(*directive, values);
+                }
+            }
+        }
+        res
+    }
+
+    /// Renders the policy the way security scanners like Mozilla Observatory expect it: one line,
+    /// sorted, single spaces and a trailing semicolon after every directive.
+    pub fn finish_scanner_format(self) -> String {
+        let policy = self.finish_string();
+        if policy.is_empty() {
+            policy
+        } else {
+            format!("{policy};")
+        }
+    }
+
+    /// Renders [CspHeaderBuilder::minified]
+    pub fn finish_minified(self) -> HeaderValue {
+        self.minified().finish()
+    }
+
+    pub fn finish(self) -> HeaderValue {
+        HeaderValue::from_str(&self.finish_string())
+            .expect("Failed to build header value from directive strings")
+    }
+
+    /// A short, stable hex digest of the rendered policy (the first 64 bits of its SHA-256), for
+    /// cache keys and spotting config drift. Equal policies always share a hash.
+    pub fn policy_hash(&self) -> String {
+        Sha256::digest(self.render(&[], true).as_bytes())
+            .iter()
+            .take(8)
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    /// Renders the policy as a string, with directives and values sorted. Unknown directives go
+    /// last, single-spaced with their values in the order they were given.
+    ///
+    /// With the `round-trip-check` feature, debug builds panic if the rendered policy doesn't
+    /// parse back into the same directives and values, to catch rendering/parsing asymmetry
+    /// during development. Values which render ambiguously fail it, like a
+    /// [CspValue::SchemeOther] of `https:` (which parses as [CspValue::SchemeHttps]). Policies
+    /// which can't round-trip by design are skipped: those with nonce placeholders (rendered as
+    /// `'none'`), trusted types sinks (lowercased when parsed), unknown directives, or values
+    /// which aren't single valid tokens.
+    pub fn finish_string(&self) -> String {
+        let res = self.render(&[], false);
+        #[cfg(feature = "round-trip-check")]
+        if cfg!(debug_assertions) {
+            self.assert_round_trip(&res);
+        }
+        res
+    }
+
+    /// Like [CspHeaderBuilder::finish], with the directives in `priority` pinned to the front in
+    /// that order, eg. for a house style putting `default-src` first. The rest follow in the
+    /// usual sort order.
+    pub fn finish_with_priority(self, priority: &[CspDirectiveType]) -> HeaderValue {
+        HeaderValue::from_str(&self.finish_string_with_priority(priority))
+            .expect("Failed to build header value from directive strings")
+    }
+
+    /// [CspHeaderBuilder::finish_with_priority], as a string
+    pub fn finish_string_with_priority(&self, priority: &[CspDirectiveType]) -> String {
+        self.render(priority, false)
+    }
+
+    /// Renders the policy, sorting unknown directives' values too if `normalize_unknown` is set,
+    /// so comparisons don't depend on how they were written
+    fn render(&self, priority: &[CspDirectiveType], normalize_unknown: bool) -> String {
+        if !self.conditional_directives.is_empty() {
+            return self
+                .resolve_conditions()
+                .render(priority, normalize_unknown);
+        }
+        let mut keys = self
+            .directive_map
+            .keys()
+            .collect::<Vec<&CspDirectiveType>>();
+        // directives not in the priority list sort after it, and then by themselves
+        keys.sort_by_key(|d| {
+            (
+                priority.iter().position(|p| p == *d).unwrap_or(usize::MAX),
+                **d,
+            )
+        });
 
         let directive_strings: Vec<String> = keys
             .iter()
@@ -312,6 +980,7 @@ impl CspHeaderBuilder {
                     None => vec![],
                 };
                 values.sort();
+                values.dedup();
                 values.into_iter().for_each(|val| {
                     directive_string.push_str(&format!(" {}", String::from(val)));
                 });
@@ -319,7 +988,23 @@ impl CspHeaderBuilder {
             })
             .collect();
 
-        HeaderValue::from_str(&directive_strings.join("; "))
-            .expect("Failed to build header value from directive strings")
+        let mut unknown = self.unknown_directives.iter().collect::<Vec<_>>();
+        unknown.sort();
+        let unknown_strings = unknown.into_iter().map(|(name, value)| {
+            let mut values = value.split_ascii_whitespace().collect::<Vec<&str>>();
+            if normalize_unknown {
+                values.sort();
+            }
+            std::iter::once(name.as_str())
+                .chain(values)
+                .collect::<Vec<&str>>()
+                .join(" ")
+        });
+
+        directive_strings
+            .into_iter()
+            .chain(unknown_strings)
+            .collect::<Vec<String>>()
+            .join("; ")
     }
 }
@@ -0,0 +1,192 @@
+//! The [csp!](crate::csp) macro, which checks a fixed policy at compile time
+
+use crate::CspDirectiveType;
+
+/// Every keyword [CspValue](crate::CspValue) parses, other than nonces and hashes
+const KEYWORDS: &[&str] = &[
+    "'none'",
+    "'self'",
+    "'strict-dynamic'",
+    "'report-sample'",
+    "'unsafe-inline'",
+    "'unsafe-eval'",
+    "'wasm-unsafe-eval'",
+    "'unsafe-hashes'",
+    "'unsafe-allow-redirects'",
+    "'inline-speculation-rules'",
+    "'trusted-types-eval'",
+    "'allow-duplicates'",
+];
+
+const fn contains(list: &[&str], value: &[u8]) -> bool {
+    let mut i = 0;
+    while i < list.len() {
+        if list[i].as_bytes().eq_ignore_ascii_case(value) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// `value` without its first `skip` and last `trim` bytes
+const fn slice(value: &[u8], skip: usize, trim: usize) -> &[u8] {
+    let (_, rest) = value.split_at(skip);
+    let (rest, _) = rest.split_at(rest.len() - trim);
+    rest
+}
+
+const fn find_directive(name: &[u8]) -> Option<CspDirectiveType> {
+    let mut i = 0;
+    while i < CspDirectiveType::ALL.len() {
+        if CspDirectiveType::ALL[i]
+            .name()
+            .as_bytes()
+            .eq_ignore_ascii_case(name)
+        {
+            return Some(CspDirectiveType::ALL[i]);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Is this a `'nonce-...'` or `'sha256-...'` style value, with a base64 part?
+const fn is_nonce_or_hash(keyword: &[u8]) -> bool {
+    let inner = slice(keyword, 1, 1);
+    let mut dash = 0;
+    while dash < inner.len() && inner[dash] != b'-' {
+        dash += 1;
+    }
+    if dash + 1 >= inner.len() {
+        return false;
+    }
+    let (prefix, rest) = inner.split_at(dash);
+    if !contains(&["nonce", "sha256", "sha384", "sha512"], prefix) {
+        return false;
+    }
+    let mut i = 1;
+    while i < rest.len() {
+        if !(rest[i].is_ascii_alphanumeric() || matches!(rest[i], b'+' | b'/' | b'-' | b'_' | b'='))
+        {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn check_value(directive: CspDirectiveType, value: &[u8]) -> Result<(), &'static str> {
+    if value.is_empty() {
+        return Err("CSP values can't be empty");
+    }
+    let mut i = 0;
+    while i < value.len() {
+        if !value[i].is_ascii() {
+            return Err("CSP values must be ASCII, so punycode any international hostnames");
+        }
+        if value[i].is_ascii_whitespace()
+            || value[i].is_ascii_control()
+            || matches!(value[i], b';' | b',')
+        {
+            return Err("CSP values can't contain whitespace, control characters, ';' or ','");
+        }
+        i += 1;
+    }
+    let quoted = value.len() >= 2 && value[0] == b'\'' && value[value.len() - 1] == b'\'';
+    if matches!(directive, CspDirectiveType::RequireTrustedTypesFor) {
+        let mut i = 1;
+        while quoted && i < value.len() - 1 {
+            if !(value[i].is_ascii_alphanumeric() || value[i] == b'-') {
+                break;
+            }
+            i += 1;
+        }
+        return match quoted && value.len() > 2 && i == value.len() - 1 {
+            true => Ok(()),
+            false => Err("sink groups must be quoted keywords, eg 'script'"),
+        };
+    }
+    if !quoted {
+        let mut i = 0;
+        while i < value.len() {
+            if value[i] == b'\'' {
+                return Err("CSP value has unbalanced quotes");
+            }
+            i += 1;
+        }
+        return Ok(());
+    }
+    if is_nonce_or_hash(value) {
+        return Ok(());
+    }
+    if !contains(KEYWORDS, value) {
+        return Err("unknown CSP keyword");
+    }
+    if directive.takes_source_list()
+        && !contains(&["'none'", "'self'"], value)
+        && !contains(directive.allowed_keywords(), value)
+    {
+        return Err("this keyword isn't allowed in this directive");
+    }
+    Ok(())
+}
+
+#[doc(hidden)]
+/// Used by [csp!](crate::csp) to check its input, which panics (at compile time) on an error
+pub const fn __check_policy(policy: &[(&str, &[&str])]) -> Result<(), &'static str> {
+    let mut i = 0;
+    while i < policy.len() {
+        let (name, values) = policy[i];
+        let Some(directive) = find_directive(name.as_bytes()) else {
+            return Err("unknown CSP directive");
+        };
+        let mut j = 0;
+        while j < values.len() {
+            if let Err(message) = check_value(directive, values[j].as_bytes()) {
+                return Err(message);
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+/// Builds a [CspHeaderBuilder](crate::CspHeaderBuilder) from a fixed policy, checked at compile
+/// time.
+///
+/// Unknown directives and keywords, malformed or non-ASCII values and keywords in a directive
+/// which doesn't allow them (per [CspDirectiveType::allowed_keywords]) are compile errors, rather
+/// than something [CspHeaderBuilder::validate](crate::CspHeaderBuilder::validate) finds at runtime.
+///
+/// ```
+/// let builder = axum_csp::csp! {
+///     "default-src" => ["'self'"],
+///     "script-src" => ["'self'", "'strict-dynamic'", "https://example.com"],
+///     "upgrade-insecure-requests" => [],
+/// };
+/// assert_eq!(
+///     builder.finish_string(),
+///     "default-src 'self'; script-src 'self' 'strict-dynamic' https://example.com; upgrade-insecure-requests"
+/// );
+/// ```
+///
+/// `'strict-dynamic'` only means something in script directives, so this doesn't compile:
+///
+/// ```compile_fail
+/// let builder = axum_csp::csp! {
+///     "img-src" => ["'self'", "'strict-dynamic'"],
+/// };
+/// ```
+#[macro_export]
+macro_rules! csp {
+    ($($directive:literal => [$($value:literal),* $(,)?]),* $(,)?) => {{
+        const _: () = match $crate::__check_policy(&[$(($directive, &[$($value),*])),*]) {
+            ::core::result::Result::Ok(()) => (),
+            ::core::result::Result::Err(message) => ::core::panic!("{}", message),
+        };
+        $crate::CspHeaderBuilder::from_header_str(::core::concat!($($directive, $(" ", $value,)* ";",)*))
+            .expect("csp! policies are checked at compile time")
+    }};
+}
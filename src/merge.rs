@@ -0,0 +1,175 @@
+//! Combining policies
+
+use crate::{CspDirectiveType, CspHeaderBuilder, CspValue, CspWarning, Severity};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// What a [MergePolicy] lets a merge add to one directive
+pub enum AllowedValues {
+    /// Anything
+    Any,
+    /// Host and scheme sources, but not keywords, nonces or hashes
+    HostsOnly,
+    /// Only these values
+    Only(Vec<CspValue>),
+}
+
+impl AllowedValues {
+    fn allows(&self, value: &CspValue) -> bool {
+        match self {
+            AllowedValues::Any => true,
+            AllowedValues::HostsOnly => value.is_host_or_scheme(),
+            AllowedValues::Only(values) => values.contains(value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The relaxations [CspHeaderBuilder::merge_guarded] may make to a base policy. Nothing is allowed
+/// until it's listed.
+pub struct MergePolicy {
+    pub allowed: HashMap<CspDirectiveType, AllowedValues>,
+}
+
+impl MergePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow adding values of this kind to a directive
+    pub fn allow(mut self, directive: CspDirectiveType, values: AllowedValues) -> Self {
+        self.allowed.insert(directive, values);
+        self
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Something a guarded merge wasn't allowed to add
+pub struct Rejection {
+    /// `None` for unknown directives, which can't be checked
+    pub directive: Option<CspDirectiveType>,
+    pub value: Option<CspValue>,
+    pub reason: String,
+}
+
+impl CspHeaderBuilder {
+    /// Merges another policy into this one, see [CspHeaderBuilder::merge_with_warnings].
+    pub fn merge(self, other: CspHeaderBuilder) -> Self {
+        self.merge_with_warnings(other).0
+    }
+
+    /// Merges another policy into this one, adding its values to each directive.
+    ///
+    /// `'none'` has to be the only value in a directive, so when the merged directive ends up
+    /// with `'none'` and real sources, `'none'` is dropped (the sources are what the other
+    /// policy asked for) and a warning says so.
+    pub fn merge_with_warnings(mut self, other: CspHeaderBuilder) -> (Self, Vec<CspWarning>) {
+        let mut entries = other.directive_map.into_iter().collect::<Vec<_>>();
+        entries.sort();
+
+        let mut warnings = vec![];
+        for (directive, values) in entries {
+            self = self.add(directive, values);
+            let merged = self
+                .directive_map
+                .get_mut(&directive)
+                .expect("directive was just added");
+            if merged.len() > 1 && merged.contains(&CspValue::None) {
+                merged.retain(|v| v != &CspValue::None);
+                warnings.push(CspWarning::new(
+                    Severity::Warning,
+                    Some(directive),
+                    "merging added sources to a directive set to 'none', so 'none' was dropped",
+                ));
+            }
+        }
+        self.annotations.extend(other.annotations);
+        self.conditional_directives
+            .extend(other.conditional_directives);
+        self.input_warnings.extend(other.input_warnings);
+        for (name, value) in other.unknown_directives {
+            self.unknown_directives.entry(name).or_insert(value);
+        }
+        (self, warnings)
+    }
+
+    /// Layers development-only relaxations over a production policy, eg. `ws://localhost:3000`
+    /// for hot reload or `'unsafe-eval'` for dev tools. Check release builds with
+    /// [CspHeaderBuilder::assert_production_safe] so they never ship.
+    ///
+    /// ```ignore
+    /// let policy = if cfg!(debug_assertions) {
+    ///     production.with_dev_overrides(dev)
+    /// } else {
+    ///     production
+    /// };
+    /// ```
+    pub fn with_dev_overrides(self, overrides: CspHeaderBuilder) -> Self {
+        self.merge(overrides)
+    }
+
+    /// Merges another policy in, but only if every value it adds is allowed by `allowed`, eg. so
+    /// sub-teams can add image hosts without touching `script-src`. Otherwise nothing is merged
+    /// and every disallowed addition is returned, in sort order.
+    ///
+    /// Values the base policy already has are always fine, and unknown directives are always
+    /// rejected since there's no telling what they relax. Conditional directives are checked like
+    /// any other, whether or not their condition currently holds.
+    pub fn merge_guarded(
+        self,
+        other: CspHeaderBuilder,
+        allowed: &MergePolicy,
+    ) -> Result<Self, Vec<Rejection>> {
+        let mut additions: BTreeMap<CspDirectiveType, Vec<CspValue>> = BTreeMap::new();
+        for (directive, values) in other.directive_map.iter().chain(
+            other
+                .conditional_directives
+                .iter()
+                .map(|conditional| (&conditional.directive, &conditional.values)),
+        ) {
+            additions
+                .entry(*directive)
+                .or_default()
+                .extend(values.iter().cloned());
+        }
+
+        let mut rejections = vec![];
+        for (directive, mut values) in additions {
+            let directive = &directive;
+            let existing = self.directive_map.get(directive);
+            values.sort();
+            values.dedup();
+            for value in values {
+                if existing.is_some_and(|existing| existing.contains(&value)) {
+                    continue;
+                }
+                let reason = match allowed.allowed.get(directive) {
+                    Some(kind) if kind.allows(&value) => continue,
+                    Some(_) => format!(
+                        "{} isn't an allowed value for {directive}",
+                        String::from(value.clone())
+                    ),
+                    None => format!("{directive} can't be changed"),
+                };
+                rejections.push(Rejection {
+                    directive: Some(*directive),
+                    value: Some(value),
+                    reason,
+                });
+            }
+        }
+        let mut unknown = other.unknown_directives.keys().collect::<Vec<&String>>();
+        unknown.sort();
+        rejections.extend(unknown.into_iter().map(|name| Rejection {
+            directive: None,
+            value: None,
+            reason: format!("unknown directive {name} can't be checked"),
+        }));
+
+        if rejections.is_empty() {
+            Ok(self.merge(other))
+        } else {
+            Err(rejections)
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Delivering a policy through a `<meta http-equiv="Content-Security-Policy">` tag
+
+use crate::{CspDirectiveType, CspHeaderBuilder, ParseError};
+
+impl CspDirectiveType {
+    /// Browsers ignore `frame-ancestors`, `report-uri` and `sandbox` when they're delivered in a
+    /// `<meta>` tag, so they have to be sent as a header.
+    pub fn is_meta_supported(&self) -> bool {
+        !matches!(
+            self,
+            CspDirectiveType::FrameAncestors
+                | CspDirectiveType::ReportUri
+                | CspDirectiveType::Sandbox
+        )
+    }
+}
+
+/// HTML-escape a string so it's safe inside a double-quoted attribute value
+fn escape_attribute(input: &str) -> String {
+    let mut res = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '"' => res.push_str("&quot;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+/// Undo [escape_attribute], plus the other common ways of writing quotes
+fn unescape_attribute(input: &str) -> String {
+    input
+        .replace("&quot;", "\"")
+        .replace("&#34;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// The `name=value` attributes of a tag, from just after the tag name up to its `>`, with names
+/// lowercased. Values can be double-quoted, single-quoted or bare.
+fn parse_attributes(tag: &str) -> Vec<(String, String)> {
+    let mut res = vec![];
+    let mut rest = tag;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+        if rest.is_empty() || rest.starts_with('>') {
+            return res;
+        }
+        let name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>'))
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+        let Some(after_equals) = rest.strip_prefix('=') else {
+            res.push((name, String::new()));
+            continue;
+        };
+        rest = after_equals.trim_start();
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = rest[1..].find(quote).map_or(rest.len(), |i| i + 1);
+                let value = &rest[1..end];
+                rest = rest.get(end + 1..).unwrap_or("");
+                value
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                    .unwrap_or(rest.len());
+                let value = &rest[..end];
+                rest = &rest[end..];
+                value
+            }
+        };
+        res.push((name, unescape_attribute(value)));
+    }
+}
+
+impl CspHeaderBuilder {
+    /// Finds the first `<meta http-equiv="Content-Security-Policy" content="...">` tag in a HTML
+    /// document and parses its policy, or returns `None` if there isn't one.
+    ///
+    /// Tag and attribute names are matched case-insensitively and values can use either quote
+    /// style. Browsers enforce every such tag; only the first is returned here.
+    pub fn from_html_meta(html: &str) -> Result<Option<Self>, ParseError> {
+        // ASCII lowercasing keeps byte offsets the same, so they work in the original too
+        let lower = html.to_ascii_lowercase();
+        let mut offset = 0;
+        while let Some(start) = lower[offset..].find("<meta") {
+            let tag_start = offset + start + "<meta".len();
+            offset = tag_start;
+            if !lower[tag_start..].starts_with(|c: char| c.is_ascii_whitespace() || c == '/') {
+                continue;
+            }
+            let attributes = parse_attributes(&html[tag_start..]);
+            let is_csp = attributes.iter().any(|(name, value)| {
+                name == "http-equiv" && value.trim().eq_ignore_ascii_case("content-security-policy")
+            });
+            if !is_csp {
+                continue;
+            }
+            if let Some((_, content)) = attributes.iter().find(|(name, _)| name == "content") {
+                return Self::from_header_str(content).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Renders the policy as a `<meta>` tag for embedding in a HTML document.
+    ///
+    /// The `content` attribute is HTML-escaped, so a malformed value (eg. a host containing `"` or `>`)
+    /// can't break out of the tag.
+    pub fn to_meta_tag(&self) -> String {
+        format!(
+            r#"<meta http-equiv="Content-Security-Policy" content="{}">"#,
+            escape_attribute(&self.finish_string())
+        )
+    }
+
+    /// Splits the policy into `(header, meta)` parts, so directives which don't work in a
+    /// `<meta>` tag are only sent in the header. Between them they cover the whole policy.
+    pub fn split_header_and_meta(self) -> (CspHeaderBuilder, CspHeaderBuilder) {
+        let mut header = CspHeaderBuilder::new();
+        let mut meta = CspHeaderBuilder::new();
+        for (directive, values) in self.directive_map {
+            let target = if directive.is_meta_supported() {
+                &mut meta
+            } else {
+                &mut header
+            };
+            target.directive_map.insert(directive, values);
+        }
+        for annotation in self.annotations {
+            let target = if annotation.directive.is_meta_supported() {
+                &mut meta
+            } else {
+                &mut header
+            };
+            target.annotations.push(annotation);
+        }
+        for conditional in self.conditional_directives {
+            let target = if conditional.directive.is_meta_supported() {
+                &mut meta
+            } else {
+                &mut header
+            };
+            target.conditional_directives.push(conditional);
+        }
+        // there's no telling whether unknown directives work in a <meta> tag
+        header.unknown_directives = self.unknown_directives;
+        (header, meta)
+    }
+}
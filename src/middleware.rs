@@ -0,0 +1,298 @@
+//! Middleware for adding policies to responses
+//!
+//! Each middleware sets its headers on the response as soon as the handler returns it, before
+//! any of the body is polled, so streaming responses and Server-Sent Events get the policy on the
+//! response head like any other response.
+
+use crate::{
+    CspDirectiveType, CspHeaderBuilder, CspUrlMatcher, Nonce, NonceSource, RandomNonceSource,
+    RenderError, ReportingConfig, REPORTING_ENDPOINTS,
+};
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::header::CONTENT_SECURITY_POLICY;
+use axum::http::{HeaderValue, Method};
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::Response;
+use axum::Router;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+#[derive(Clone)]
+/// Configuration for [csp_nonce_middleware]
+pub struct CspNonceConfig {
+    /// The policy, without the nonce
+    pub builder: CspHeaderBuilder,
+    /// The directives the per-request nonce is added to
+    pub directives: Vec<CspDirectiveType>,
+    pub nonce_source: Arc<dyn NonceSource>,
+    /// If set, `report-to` and the `Reporting-Endpoints` header are added to responses
+    pub reporting: Option<ReportingConfig>,
+}
+
+impl Debug for CspNonceConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CspNonceConfig")
+            .field("builder", &self.builder)
+            .field("directives", &self.directives)
+            .field("reporting", &self.reporting)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CspNonceConfig {
+    /// Uses [RandomNonceSource] to generate nonces
+    pub fn new(builder: CspHeaderBuilder, directives: Vec<CspDirectiveType>) -> Self {
+        Self {
+            builder,
+            directives,
+            nonce_source: Arc::new(RandomNonceSource),
+            reporting: None,
+        }
+    }
+
+    /// Adds `report-to` and the `Reporting-Endpoints` header to responses, failing if the group
+    /// name or URL can't go in the header
+    pub fn with_reporting(mut self, reporting: ReportingConfig) -> Result<Self, RenderError> {
+        reporting.endpoints_header_value()?;
+        self.reporting = Some(reporting);
+        Ok(self)
+    }
+
+    pub fn with_nonce_source(mut self, nonce_source: impl NonceSource + 'static) -> Self {
+        self.nonce_source = Arc::new(nonce_source);
+        self
+    }
+
+    /// The policy to send with a given nonce, any [crate::CspValue::NoncePlaceholder] values in
+    /// the policy are resolved to it too.
+    pub fn policy_for(&self, nonce: &Nonce) -> CspHeaderBuilder {
+        let builder = match &self.reporting {
+            Some(reporting) => reporting.apply_to(self.builder.resolve_conditions()),
+            None => self.builder.resolve_conditions(),
+        }
+        .resolve_nonce_placeholders(nonce);
+        self.directives.iter().fold(builder, |builder, directive| {
+            builder.add(*directive, vec![nonce.into()])
+        })
+    }
+}
+
+/// Adds a `Content-Security-Policy` header with a fresh nonce to every response, and puts the
+/// [Nonce] in the request extensions for handlers to use.
+///
+/// Exactly one nonce is generated per request, and it's the same one in every configured
+/// directive and in the extensions, so scripts and styles on a page share it.
+///
+/// ```ignore
+/// let config = CspNonceConfig::new(builder, vec![CspDirectiveType::ScriptSource]);
+/// let router = Router::new()
+///     .route("/", get(home))
+///     .layer(from_fn_with_state(config, csp_nonce_middleware));
+/// ```
+pub async fn csp_nonce_middleware(
+    State(config): State<CspNonceConfig>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let nonce = config.nonce_source.generate();
+    let header = config.policy_for(&nonce).finish();
+    req.extensions_mut().insert(nonce);
+
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_SECURITY_POLICY, header);
+    // with_reporting checks this, so it only fails if the field was set to something invalid
+    if let Some(Ok(endpoints)) = config
+        .reporting
+        .as_ref()
+        .map(|r| r.endpoints_header_value())
+    {
+        headers.insert(REPORTING_ENDPOINTS, endpoints);
+    }
+    response
+}
+
+/// Adds the same `Content-Security-Policy` header to every response
+pub async fn csp_static_middleware(
+    State(header): State<HeaderValue>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(req).await;
+    response
+        .headers_mut()
+        .insert(CONTENT_SECURITY_POLICY, header);
+    response
+}
+
+/// Renders the policy for each response, so [CspHeaderBuilder::add_conditional] directives follow
+/// their flags
+pub async fn csp_dynamic_middleware(
+    State(builder): State<Arc<CspHeaderBuilder>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let header = HeaderValue::from_str(&builder.finish_string())
+        .expect("Failed to build header value from directive strings");
+    let mut response = next.run(req).await;
+    response
+        .headers_mut()
+        .insert(CONTENT_SECURITY_POLICY, header);
+    response
+}
+
+#[derive(Clone, Debug)]
+/// Configuration for [csp_matcher_middleware], picking a policy per request from a list of
+/// [CspUrlMatcher]s. The first matcher that matches the request's method and path wins.
+pub struct CspMatcherConfig {
+    matchers: Arc<Vec<MatcherEntry>>,
+    /// Match against the route template from [MatchedPath] rather than the request path
+    pub use_matched_path: bool,
+}
+
+#[derive(Clone, Debug)]
+struct MatcherEntry {
+    method: Option<Method>,
+    matcher: CspUrlMatcher,
+    header: Arc<HeaderValue>,
+}
+
+impl CspMatcherConfig {
+    /// Renders each matcher's policy up front, so requests only have to find the match. The
+    /// matchers apply to requests with any method, see [CspMatcherConfig::with_methods].
+    pub fn new(matchers: Vec<CspUrlMatcher>) -> Self {
+        Self::with_methods(matchers.into_iter().map(|m| (None, m)).collect())
+    }
+
+    /// [CspMatcherConfig::new], where a matcher paired with a method only matches requests with
+    /// that method, eg. for a stricter policy on `POST` endpoints
+    pub fn with_methods(matchers: Vec<(Option<Method>, CspUrlMatcher)>) -> Self {
+        let (methods, matchers): (Vec<Option<Method>>, Vec<CspUrlMatcher>) =
+            matchers.into_iter().unzip();
+        let headers = CspUrlMatcher::shared_headers(&matchers);
+        Self {
+            matchers: Arc::new(
+                methods
+                    .into_iter()
+                    .zip(matchers)
+                    .zip(headers)
+                    .map(|((method, matcher), header)| MatcherEntry {
+                        method,
+                        matcher,
+                        header,
+                    })
+                    .collect(),
+            ),
+            use_matched_path: false,
+        }
+    }
+
+    /// Match against the route template (eg. `/users/{id}`) from axum's [MatchedPath] instead of
+    /// the request path, so policies map to routes rather than concrete paths. Requests which
+    /// didn't match a route fall back to their path.
+    pub fn with_matched_path(mut self) -> Self {
+        self.use_matched_path = true;
+        self
+    }
+
+    /// The policy for a request, if any matcher matches it
+    pub fn header_for(&self, method: &Method, path: &str) -> Option<&HeaderValue> {
+        self.matchers
+            .iter()
+            .find(|entry| {
+                entry.method.as_ref().is_none_or(|m| m == method) && entry.matcher.is_match(path)
+            })
+            .map(|entry| entry.header.as_ref())
+    }
+}
+
+/// Adds the policy of the first [CspUrlMatcher] matching the request, leaving responses to
+/// requests nothing matches alone
+///
+/// ```ignore
+/// let config =
+///     CspMatcherConfig::with_methods(vec![(Some(Method::POST), strict), (None, default)]);
+/// let router = Router::new()
+///     .route("/x", get(show).post(update))
+///     .layer(from_fn_with_state(config, csp_matcher_middleware));
+/// ```
+pub async fn csp_matcher_middleware(
+    State(config): State<CspMatcherConfig>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = match req.extensions().get::<MatchedPath>() {
+        Some(matched) if config.use_matched_path => matched.as_str(),
+        _ => req.uri().path(),
+    };
+    let header = config.header_for(req.method(), path).cloned();
+    let mut response = next.run(req).await;
+    if let Some(header) = header {
+        response
+            .headers_mut()
+            .insert(CONTENT_SECURITY_POLICY, header);
+    }
+    response
+}
+
+#[derive(Clone, Debug)]
+/// Which middleware [RouterCspExt::with_csp] installs, usually made with `.into()` from a
+/// [CspHeaderBuilder] or a [CspNonceConfig]
+// built once per router, so the size of the nonce config doesn't matter
+#[allow(clippy::large_enum_variant)]
+pub enum CspMiddlewareConfig {
+    /// The policy is rendered once and sent as-is, with [csp_static_middleware]
+    Static(HeaderValue),
+    /// The policy has conditional directives, so it's rendered per request with
+    /// [csp_dynamic_middleware]
+    Dynamic(Arc<CspHeaderBuilder>),
+    /// A fresh nonce is added per request, with [csp_nonce_middleware]
+    Nonce(CspNonceConfig),
+}
+
+impl From<CspHeaderBuilder> for CspMiddlewareConfig {
+    fn from(input: CspHeaderBuilder) -> CspMiddlewareConfig {
+        if input.conditional_directives.is_empty() {
+            CspMiddlewareConfig::Static(input.finish())
+        } else {
+            CspMiddlewareConfig::Dynamic(Arc::new(input))
+        }
+    }
+}
+
+impl From<CspNonceConfig> for CspMiddlewareConfig {
+    fn from(input: CspNonceConfig) -> CspMiddlewareConfig {
+        CspMiddlewareConfig::Nonce(input)
+    }
+}
+
+/// Installs the CSP middleware on a whole [Router], without the `from_fn_with_state` wiring
+///
+/// ```ignore
+/// let router = Router::new()
+///     .route("/", get(home))
+///     .with_csp(CspHeaderBuilder::new().add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]));
+/// ```
+pub trait RouterCspExt {
+    /// Adds the policy to every response from routes added so far, as with [Router::layer]
+    fn with_csp(self, csp: impl Into<CspMiddlewareConfig>) -> Self;
+}
+
+impl<S> RouterCspExt for Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn with_csp(self, csp: impl Into<CspMiddlewareConfig>) -> Self {
+        match csp.into() {
+            CspMiddlewareConfig::Static(header) => {
+                self.layer(from_fn_with_state(header, csp_static_middleware))
+            }
+            CspMiddlewareConfig::Dynamic(builder) => {
+                self.layer(from_fn_with_state(builder, csp_dynamic_middleware))
+            }
+            CspMiddlewareConfig::Nonce(config) => {
+                self.layer(from_fn_with_state(config, csp_nonce_middleware))
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Per-request nonces for `'nonce-...'` sources
+
+use crate::{CspHeaderBuilder, CspValue};
+use axum::http::HeaderValue;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE};
+use base64::Engine;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A base64-encoded nonce, generate a fresh one for every response.
+///
+/// The middleware puts the one it used in the request extensions, so handlers can add it to
+/// their `<script nonce="...">` tags.
+pub struct Nonce(String);
+
+impl Nonce {
+    /// Wrap an already-encoded nonce value
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// The encoded value, as it goes in the `nonce` attribute
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Nonce {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Nonce> for CspValue {
+    fn from(input: Nonce) -> CspValue {
+        CspValue::Nonce { value: input.0 }
+    }
+}
+
+impl From<&Nonce> for CspValue {
+    fn from(input: &Nonce) -> CspValue {
+        CspValue::Nonce {
+            value: input.0.clone(),
+        }
+    }
+}
+
+/// Something which makes nonces, implement this to control length/encoding or to get
+/// deterministic nonces in tests.
+pub trait NonceSource: Send + Sync {
+    fn generate(&self) -> Nonce;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+/// The default [NonceSource], 128 bits from the operating system's CSPRNG, base64-encoded. Use
+/// [NonceConfig] for a different length or encoding.
+pub struct RandomNonceSource;
+
+impl NonceSource for RandomNonceSource {
+    fn generate(&self) -> Nonce {
+        let mut bytes = [0u8; 16];
+        getrandom::fill(&mut bytes).expect("Failed to get random bytes for a nonce");
+        Nonce(STANDARD.encode(bytes))
+    }
+}
+
+/// The shortest nonce [NonceConfig] allows, 128 bits as the CSP spec recommends
+pub const MIN_NONCE_BYTES: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+/// How nonce bytes are turned into a string, both are valid in a `'nonce-...'` source
+pub enum NonceEncoding {
+    /// Standard base64, with `+`, `/` and padding
+    #[default]
+    Base64,
+    /// URL-safe base64, with `-`, `_` and padding
+    Base64Url,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A nonce length shorter than [MIN_NONCE_BYTES]
+pub struct NonceLengthError {
+    pub byte_length: usize,
+}
+
+impl Display for NonceLengthError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "nonces need at least {MIN_NONCE_BYTES} bytes, got {}",
+            self.byte_length
+        )
+    }
+}
+
+impl std::error::Error for NonceLengthError {}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct NonceConfigFields {
+    byte_length: usize,
+    #[serde(default)]
+    encoding: NonceEncoding,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "NonceConfigFields"))]
+/// A [NonceSource] making random nonces of a configured length and encoding, eg. 256 bits where
+/// that's required. With the `serde` feature it can be read from configuration, and lengths
+/// under [MIN_NONCE_BYTES] are rejected there too.
+pub struct NonceConfig {
+    byte_length: usize,
+    encoding: NonceEncoding,
+}
+
+impl Default for NonceConfig {
+    /// The same as [RandomNonceSource], 128 bits in standard base64
+    fn default() -> Self {
+        Self {
+            byte_length: MIN_NONCE_BYTES,
+            encoding: NonceEncoding::Base64,
+        }
+    }
+}
+
+impl NonceConfig {
+    pub fn new(byte_length: usize) -> Result<Self, NonceLengthError> {
+        if byte_length < MIN_NONCE_BYTES {
+            return Err(NonceLengthError { byte_length });
+        }
+        Ok(Self {
+            byte_length,
+            encoding: NonceEncoding::Base64,
+        })
+    }
+
+    pub fn with_encoding(mut self, encoding: NonceEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn byte_length(&self) -> usize {
+        self.byte_length
+    }
+
+    pub fn encoding(&self) -> NonceEncoding {
+        self.encoding
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<NonceConfigFields> for NonceConfig {
+    type Error = NonceLengthError;
+
+    fn try_from(input: NonceConfigFields) -> Result<Self, Self::Error> {
+        Ok(Self::new(input.byte_length)?.with_encoding(input.encoding))
+    }
+}
+
+impl NonceSource for NonceConfig {
+    fn generate(&self) -> Nonce {
+        let mut bytes = vec![0u8; self.byte_length];
+        getrandom::fill(&mut bytes).expect("Failed to get random bytes for a nonce");
+        Nonce(match self.encoding {
+            NonceEncoding::Base64 => STANDARD.encode(bytes),
+            NonceEncoding::Base64Url => URL_SAFE.encode(bytes),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The nonce for the current response, used to resolve [CspValue::NoncePlaceholder]
+pub struct NonceContext {
+    pub nonce: Nonce,
+}
+
+impl NonceContext {
+    pub fn new(nonce: Nonce) -> Self {
+        Self { nonce }
+    }
+}
+
+impl CspHeaderBuilder {
+    /// Replaces every [CspValue::NoncePlaceholder] with the given nonce
+    pub fn resolve_nonce_placeholders(mut self, nonce: &Nonce) -> Self {
+        for values in self.directive_map.values_mut() {
+            if values.contains(&CspValue::NoncePlaceholder) {
+                values.retain(|v| v != &CspValue::NoncePlaceholder);
+                let value = CspValue::from(nonce);
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+        }
+        self
+    }
+
+    /// Like [CspHeaderBuilder::finish], with placeholders replaced by the context's nonce
+    pub fn finish_with_context(self, ctx: &NonceContext) -> HeaderValue {
+        self.resolve_nonce_placeholders(&ctx.nonce).finish()
+    }
+}
@@ -0,0 +1,370 @@
+//! Turning strings back into policies
+
+use crate::{CspDirectiveType, CspHeaderBuilder, CspValue};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Something went wrong turning a string into part of a policy
+pub enum ParseError {
+    /// The directive name isn't one we know about
+    UnknownDirective { name: String },
+    /// A source value couldn't be parsed
+    InvalidValue { value: String, reason: String },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnknownDirective { name } => {
+                write!(f, "unknown directive {name:?}")?;
+                match self.suggestion() {
+                    Some(suggestion) => write!(f, ", did you mean `{suggestion}`?"),
+                    None => Ok(()),
+                }
+            }
+            ParseError::InvalidValue { value, reason } => {
+                write!(f, "invalid value {value:?}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// How many single-character edits it takes to turn one string into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl ParseError {
+    /// For an unknown directive, the known directive it's probably a typo of, within two edits
+    pub fn suggestion(&self) -> Option<CspDirectiveType> {
+        let ParseError::UnknownDirective { name } = self else {
+            return None;
+        };
+        let name = name.to_ascii_lowercase();
+        CspDirectiveType::ALL
+            .iter()
+            .map(|d| (edit_distance(&name, d.as_ref()), *d))
+            .filter(|(distance, _)| *distance <= 2)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, d)| d)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Every problem found while loading a policy from configuration
+pub struct ConfigError {
+    pub errors: Vec<ParseError>,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} error(s) in CSP configuration: {}",
+            self.errors.len(),
+            self.errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl FromStr for CspDirectiveType {
+    type Err = ParseError;
+
+    /// Directive names are matched case-insensitively, as browsers do
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        CspDirectiveType::ALL
+            .iter()
+            .find(|d| d.as_ref() == name)
+            .copied()
+            .ok_or_else(|| ParseError::UnknownDirective {
+                name: s.trim().to_string(),
+            })
+    }
+}
+
+fn invalid(value: &str, reason: &str) -> ParseError {
+    ParseError::InvalidValue {
+        value: value.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Is this a `scheme:` source, per the RFC 3986 scheme grammar?
+fn is_scheme_source(value: &str) -> bool {
+    match value.strip_suffix(':') {
+        Some(scheme) => {
+            let mut chars = scheme.chars();
+            chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+impl FromStr for CspValue {
+    type Err = ParseError;
+
+    /// Parses a single source expression, eg `'self'`, `https:` or `https://example.com`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        if value.is_empty() {
+            return Err(invalid(s, "value is empty"));
+        }
+        if value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == ';' || c == ',')
+        {
+            return Err(invalid(
+                s,
+                "values can't contain whitespace, control characters, ';' or ','",
+            ));
+        }
+
+        if let Some(keyword) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+            let lower = keyword.to_ascii_lowercase();
+            return match lower.as_str() {
+                "none" => Ok(CspValue::None),
+                "self" => Ok(CspValue::SelfSite),
+                "strict-dynamic" => Ok(CspValue::StrictDynamic),
+                "report-sample" => Ok(CspValue::ReportSample),
+                "unsafe-inline" => Ok(CspValue::UnsafeInline),
+                "unsafe-eval" => Ok(CspValue::UnsafeEval),
+                "wasm-unsafe-eval" => Ok(CspValue::WasmUnsafeEval),
+                "unsafe-hashes" => Ok(CspValue::UnsafeHashes),
+                "unsafe-allow-redirects" => Ok(CspValue::UnsafeAllowRedirects),
+                "inline-speculation-rules" => Ok(CspValue::InlineSpeculationRules),
+                "trusted-types-eval" => Ok(CspValue::TrustedTypesEval),
+                "allow-duplicates" => Ok(CspValue::AllowDuplicates),
+                _ => {
+                    // the base64 part of nonces and hashes is case-sensitive, so slice the original
+                    let (prefix, rest) = match keyword.split_once('-') {
+                        Some((prefix, rest)) if !rest.is_empty() => {
+                            (prefix.to_ascii_lowercase(), rest.to_string())
+                        }
+                        _ => return Err(invalid(s, "unknown keyword")),
+                    };
+                    if !rest.chars().all(|c| {
+                        c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '-' | '_' | '=')
+                    }) {
+                        return Err(invalid(s, "nonces and hashes must be base64"));
+                    }
+                    match prefix.as_str() {
+                        "nonce" => Ok(CspValue::Nonce { value: rest }),
+                        "sha256" => Ok(CspValue::Sha256 { value: rest }),
+                        "sha384" => Ok(CspValue::Sha384 { value: rest }),
+                        "sha512" => Ok(CspValue::Sha512 { value: rest }),
+                        _ => Err(invalid(s, "unknown keyword")),
+                    }
+                }
+            };
+        }
+        if value.contains('\'') {
+            return Err(invalid(s, "unbalanced quotes"));
+        }
+
+        if is_scheme_source(value) {
+            return Ok(match value.to_ascii_lowercase().as_str() {
+                "https:" => CspValue::SchemeHttps,
+                "http:" => CspValue::SchemeHttp,
+                "data:" => CspValue::SchemeData,
+                _ => CspValue::SchemeOther {
+                    value: value.to_string(),
+                },
+            });
+        }
+
+        Ok(CspValue::Host {
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Parses a value in the context of its directive, for directives which don't take source lists
+fn parse_directive_value(
+    directive: CspDirectiveType,
+    token: &str,
+    strict: bool,
+) -> Result<CspValue, ParseError> {
+    match directive {
+        CspDirectiveType::RequireTrustedTypesFor => {
+            let sink = token
+                .strip_prefix('\'')
+                .and_then(|v| v.strip_suffix('\''))
+                .filter(|v| {
+                    !v.is_empty() && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                })
+                .ok_or_else(|| {
+                    invalid(token, "sink groups must be quoted keywords, eg 'script'")
+                })?;
+            match sink.to_ascii_lowercase().as_str() {
+                "script" => Ok(CspValue::ScriptSink),
+                _ if strict => Err(invalid(token, "unknown sink group")),
+                sink => Ok(CspValue::OtherSink {
+                    value: sink.to_string(),
+                }),
+            }
+        }
+        _ => CspValue::from_str(token),
+    }
+}
+
+/// Parses each `;`-separated directive of a policy, skipping empty ones
+fn parse_directives(
+    policy: &str,
+    strict: bool,
+) -> impl Iterator<Item = Result<(CspDirectiveType, Vec<CspValue>), ParseError>> + '_ {
+    policy.split(';').filter_map(move |directive_string| {
+        let mut tokens = directive_string.split_ascii_whitespace();
+        let name = tokens.next()?;
+        Some(CspDirectiveType::from_str(name).and_then(|directive| {
+            tokens
+                .map(|token| parse_directive_value(directive, token, strict))
+                .collect::<Result<Vec<CspValue>, ParseError>>()
+                .map(|values| (directive, values))
+        }))
+    })
+}
+
+impl CspHeaderBuilder {
+    /// Parses a `Content-Security-Policy` header value.
+    ///
+    /// As in browsers, directive names are case-insensitive and only the first occurrence of a
+    /// duplicated directive is used.
+    pub fn from_header_str(policy: &str) -> Result<Self, ParseError> {
+        Self::parse_policy(policy, false)
+    }
+
+    /// Like [CspHeaderBuilder::from_header_str], but rejects values which are only accepted for
+    /// forwards compatibility, like unknown `require-trusted-types-for` sink groups.
+    pub fn from_header_str_strict(policy: &str) -> Result<Self, ParseError> {
+        Self::parse_policy(policy, true)
+    }
+
+    /// Like [CspHeaderBuilder::from_header_str], but a broken directive is skipped rather than
+    /// failing the whole policy, for auditing real-world policies. The errors for the skipped
+    /// directives are returned alongside everything which did parse.
+    pub fn from_header_str_lossy(policy: &str) -> (Self, Vec<ParseError>) {
+        let mut builder = CspHeaderBuilder::new();
+        let mut errors = vec![];
+        for directive in parse_directives(policy, false) {
+            match directive {
+                Ok((directive, values)) => {
+                    if !builder.directive_map.contains_key(&directive) {
+                        builder = builder.add(directive, values);
+                    }
+                }
+                Err(err) => errors.push(err),
+            }
+        }
+        (builder, errors)
+    }
+
+    fn parse_policy(policy: &str, strict: bool) -> Result<Self, ParseError> {
+        let mut builder = CspHeaderBuilder::new();
+        for directive in parse_directives(policy, strict) {
+            let (directive, values) = directive?;
+            if !builder.directive_map.contains_key(&directive) {
+                builder = builder.add(directive, values);
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Builds a policy from configuration shaped like `{"script-src": ["'self'", "https:"]}`.
+    ///
+    /// Values are parsed as [CspHeaderBuilder::from_header_str] parses them, so unknown
+    /// `require-trusted-types-for` sinks are kept. Every bad key and value is reported, not just
+    /// the first one.
+    pub fn try_from_map(map: HashMap<String, Vec<String>>) -> Result<Self, ConfigError> {
+        let mut entries: Vec<(String, Vec<String>)> = map.into_iter().collect();
+        entries.sort();
+
+        let mut builder = CspHeaderBuilder::new();
+        let mut errors = vec![];
+        for (name, raw_values) in entries {
+            let directive = CspDirectiveType::from_str(&name)
+                .map_err(|e| errors.push(e))
+                .ok();
+            let values: Vec<CspValue> = raw_values
+                .iter()
+                .filter_map(|v| {
+                    match directive {
+                        Some(directive) => parse_directive_value(directive, v, false),
+                        None => CspValue::from_str(v),
+                    }
+                    .map_err(|e| errors.push(e))
+                    .ok()
+                })
+                .collect();
+            if let Some(directive) = directive {
+                builder = builder.add(directive, values);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(builder)
+        } else {
+            Err(ConfigError { errors })
+        }
+    }
+
+    /// Panics if `policy`, this policy as rendered by [CspHeaderBuilder::finish_string], doesn't
+    /// parse back into the same directives and values. Run by `finish_string` in debug builds
+    /// with the `round-trip-check` feature.
+    #[cfg(feature = "round-trip-check")]
+    pub(crate) fn assert_round_trip(&self, policy: &str) {
+        let builder = self.resolve_conditions();
+        let skip = !builder.unknown_directives.is_empty()
+            || builder.directive_map.values().flatten().any(|value| {
+                matches!(
+                    value,
+                    CspValue::NoncePlaceholder | CspValue::ScriptSink | CspValue::OtherSink { .. }
+                ) || crate::render::check_token(&String::from(value.to_owned())).is_some()
+            });
+        if skip {
+            return;
+        }
+        let normalized = |builder: &CspHeaderBuilder| {
+            let mut res = builder
+                .directive_map
+                .iter()
+                .map(|(directive, values)| {
+                    let mut values = values.clone();
+                    values.sort();
+                    values.dedup();
+                    (*directive, values)
+                })
+                .collect::<Vec<_>>();
+            res.sort();
+            res
+        };
+        let parsed = CspHeaderBuilder::from_header_str(policy)
+            .unwrap_or_else(|err| panic!("rendered policy {policy:?} doesn't parse: {err}"));
+        assert_eq!(
+            normalized(&builder),
+            normalized(&parsed),
+            "rendered policy {policy:?} doesn't parse back into the same policy"
+        );
+    }
+}
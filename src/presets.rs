@@ -0,0 +1,86 @@
+//! Ready-made policies for common situations
+
+use crate::{CspDirectiveType, CspHeaderBuilder, CspValue, Nonce};
+
+impl CspHeaderBuilder {
+    /// The common web-app starting point: `default-src 'self'` plus the given `script-src`,
+    /// `style-src` and `img-src` values.
+    pub fn web_app(script: Vec<CspValue>, style: Vec<CspValue>, img: Vec<CspValue>) -> Self {
+        Self::new()
+            .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+            .add(CspDirectiveType::ScriptSource, script)
+            .add(CspDirectiveType::StyleSource, style)
+            .add(CspDirectiveType::ImgSrc, img)
+    }
+
+    /// A locked-down policy for endpoints which never serve HTML, eg. JSON APIs, as defence in
+    /// depth: `default-src 'none'; frame-ancestors 'none'; base-uri 'none'; form-action 'none'`
+    pub fn api_only() -> Self {
+        Self::new()
+            .add(CspDirectiveType::DefaultSrc, vec![CspValue::None])
+            .add(CspDirectiveType::FrameAncestors, vec![CspValue::None])
+            .add(CspDirectiveType::BaseUri, vec![CspValue::None])
+            .add(CspDirectiveType::FormAction, vec![CspValue::None])
+    }
+
+    /// A policy for serving untrusted user content, like uploads: `default-src 'none'; sandbox`,
+    /// so nothing loads and the document is fully sandboxed (a unique origin, no scripts, forms,
+    /// popups or navigation).
+    ///
+    /// Restrictions can be lifted by adding [SandboxToken](crate::SandboxToken)s, eg.
+    /// `.add(CspDirectiveType::Sandbox, vec![SandboxToken::AllowDownloads.into()])`.
+    pub fn sandboxed_content() -> Self {
+        Self::new()
+            .add(CspDirectiveType::DefaultSrc, vec![CspValue::None])
+            .add(CspDirectiveType::Sandbox, vec![])
+    }
+
+    /// A policy for high-security pages, where scripts and styles only run if they carry the
+    /// nonce: `default-src 'none'; base-uri 'none'; object-src 'none'; script-src 'nonce-...';
+    /// style-src 'nonce-...'`, with no host allowlists or `'unsafe-*'` keywords.
+    ///
+    /// Without a nonce the script and style directives are left out, so nothing runs until you
+    /// add hashes to them.
+    ///
+    /// Use [CspHeaderBuilder::assert_integrity_only] to check nothing added later weakens it.
+    pub fn hash_only(nonce: Option<&Nonce>) -> Self {
+        let builder = Self::new()
+            .add(CspDirectiveType::DefaultSrc, vec![CspValue::None])
+            .add(CspDirectiveType::BaseUri, vec![CspValue::None])
+            .add(CspDirectiveType::ObjectSrc, vec![CspValue::None]);
+        match nonce {
+            Some(nonce) => builder
+                .add(CspDirectiveType::ScriptSource, vec![nonce.into()])
+                .add(CspDirectiveType::StyleSource, vec![nonce.into()]),
+            None => builder,
+        }
+    }
+
+    /// Adds the fallbacks from the "hybrid" strict CSP to `script-src`, so it degrades gracefully
+    /// in older browsers:
+    ///
+    /// - with a nonce or hash, `'unsafe-inline'` for browsers without nonce support, which newer
+    ///   browsers ignore when a nonce is present
+    /// - with `'strict-dynamic'`, `https:` and `http:` for browsers without it, which newer
+    ///   browsers ignore in favour of `'strict-dynamic'`
+    ///
+    /// Fallbacks are only added when the thing that neutralises them is there, so this never
+    /// weakens the policy in modern browsers.
+    pub fn with_legacy_fallbacks(mut self) -> Self {
+        if let Some(values) = self.directive_map.get_mut(&CspDirectiveType::ScriptSource) {
+            let mut fallbacks = vec![];
+            if values.iter().any(|v| v.is_nonce_or_hash()) {
+                fallbacks.push(CspValue::UnsafeInline);
+            }
+            if values.contains(&CspValue::StrictDynamic) {
+                fallbacks.extend([CspValue::SchemeHttps, CspValue::SchemeHttp]);
+            }
+            for fallback in fallbacks {
+                if !values.contains(&fallback) {
+                    values.push(fallback);
+                }
+            }
+        }
+        self
+    }
+}
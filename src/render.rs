@@ -0,0 +1,130 @@
+//! Rendering policies, with checks
+
+use crate::{CspDirective, CspDirectiveType, CspHeaderBuilder, CspValue};
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A value which can't be rendered into a valid header
+pub struct RenderError {
+    pub directive: CspDirectiveType,
+    pub value: CspValue,
+    pub reason: String,
+}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "can't render {:?} in {}: {}",
+            String::from(self.value.to_owned()),
+            self.directive,
+            self.reason
+        )
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A rendered policy which isn't all visible ASCII, see [CspHeaderBuilder::finish_bytes]
+pub struct FinishError {
+    /// The offending character
+    pub character: char,
+    /// Where it is in the rendered policy, in bytes
+    pub position: usize,
+}
+
+impl Display for FinishError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "policy contains {:?} at byte {}, only visible ASCII is allowed",
+            self.character, self.position
+        )
+    }
+}
+
+impl std::error::Error for FinishError {}
+
+/// Why a rendered value isn't a single valid token, if it isn't
+pub(crate) fn check_token(rendered: &str) -> Option<String> {
+    if rendered.is_empty() {
+        return Some("value is empty".to_string());
+    }
+    rendered.chars().find_map(|c| match c {
+        ' ' | '\t' => {
+            Some("contains whitespace, which would split it into multiple values".to_string())
+        }
+        ';' | ',' => Some(format!("contains {c:?}, which would end the directive")),
+        c if !c.is_ascii() || c.is_ascii_control() => Some(format!(
+            "contains {c:?}, only visible ASCII is allowed in a header"
+        )),
+        _ => None,
+    })
+}
+
+impl CspDirective {
+    /// Like `to_string()`, but checks every value renders as a single valid header token and says
+    /// which one doesn't.
+    pub fn render_checked(&self) -> Result<String, RenderError> {
+        let mut res = self.directive_type.to_string();
+        for value in &self.values {
+            let rendered = String::from(value.to_owned());
+            if let Some(reason) = check_token(&rendered) {
+                return Err(RenderError {
+                    directive: self.directive_type,
+                    value: value.to_owned(),
+                    reason,
+                });
+            }
+            res.push(' ');
+            res.push_str(&rendered);
+        }
+        Ok(res)
+    }
+}
+
+impl CspHeaderBuilder {
+    /// The policy as structured log fields, one `("csp.<directive>", "<values>")` pair per
+    /// directive, sorted and de-duplicated as in [CspHeaderBuilder::finish_string]. Directives
+    /// without values, like `upgrade-insecure-requests`, get an empty string.
+    pub fn as_fields(&self) -> Vec<(String, String)> {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .collect::<Vec<&CspDirectiveType>>();
+        keys.sort();
+        keys.into_iter()
+            .map(|directive| {
+                let mut values = self.directive_map[directive].to_owned();
+                values.sort();
+                values.dedup();
+                (
+                    format!("csp.{directive}"),
+                    values
+                        .into_iter()
+                        .map(String::from)
+                        .collect::<Vec<String>>()
+                        .join(" "),
+                )
+            })
+            .collect()
+    }
+
+    /// The canonical policy from [CspHeaderBuilder::finish_string] as bytes, for HTTP stacks which
+    /// take headers as raw bytes. The CSP grammar is ASCII, so this fails on anything but visible
+    /// ASCII and spaces, rather than sending bytes browsers would reject.
+    pub fn finish_bytes(self) -> Result<Vec<u8>, FinishError> {
+        let policy = self.finish_string();
+        match policy
+            .char_indices()
+            .find(|(_, c)| !(c.is_ascii_graphic() || *c == ' '))
+        {
+            Some((position, character)) => Err(FinishError {
+                character,
+                position,
+            }),
+            None => Ok(policy.into_bytes()),
+        }
+    }
+}
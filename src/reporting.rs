@@ -0,0 +1,149 @@
+//! Violation reporting
+
+use crate::render::check_token;
+use crate::{CspDirective, CspDirectiveType, CspHeaderBuilder, CspValue, RenderError};
+use axum::http::{HeaderName, HeaderValue};
+
+/// The [`Reporting-Endpoints`](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Reporting-Endpoints) header
+pub const REPORTING_ENDPOINTS: HeaderName = HeaderName::from_static("reporting-endpoints");
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A reporting group and where it sends reports, which produces both the `report-to` directive
+/// and the `Reporting-Endpoints` header so they can't get out of sync.
+pub struct ReportingConfig {
+    pub group: String,
+    pub url: String,
+}
+
+impl ReportingConfig {
+    pub fn new(group: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            group: group.into(),
+            url: url.into(),
+        }
+    }
+
+    /// The group name, as it goes in `report-to`
+    pub fn value(&self) -> CspValue {
+        CspValue::Host {
+            value: self.group.clone(),
+        }
+    }
+
+    /// The `report-to` directive
+    pub fn directive(&self) -> CspDirective {
+        CspDirective::from(CspDirectiveType::ReportTo, vec![self.value()])
+    }
+
+    /// The value for the [REPORTING_ENDPOINTS] header, eg. `csp-endpoint="https://example.com/csp"`
+    ///
+    /// The header is a structured-field dictionary, so this fails unless the group is a valid
+    /// key (lowercase letters, digits, `_`, `-`, `.` and `*`, starting with a letter or `*`) and
+    /// the URL is printable ASCII.
+    pub fn endpoints_header_value(&self) -> Result<HeaderValue, RenderError> {
+        let error = |value: &str, reason: &str| RenderError {
+            directive: CspDirectiveType::ReportTo,
+            value: CspValue::Host {
+                value: value.to_string(),
+            },
+            reason: reason.to_string(),
+        };
+        let is_key_start = |c: char| c.is_ascii_lowercase() || c == '*';
+        let is_key = self.group.starts_with(is_key_start)
+            && self
+                .group
+                .chars()
+                .all(|c| is_key_start(c) || c.is_ascii_digit() || matches!(c, '_' | '-' | '.'));
+        if !is_key {
+            return Err(error(
+                &self.group,
+                "reporting group names must be lowercase structured-field keys",
+            ));
+        }
+        if let Some(reason) = check_token(&self.group) {
+            return Err(error(&self.group, &reason));
+        }
+
+        let mut url = String::with_capacity(self.url.len());
+        for c in self.url.chars() {
+            match c {
+                '"' | '\\' => {
+                    url.push('\\');
+                    url.push(c);
+                }
+                ' '..='~' => url.push(c),
+                _ => {
+                    return Err(error(
+                        &self.url,
+                        &format!("contains {c:?}, only printable ASCII is allowed"),
+                    ))
+                }
+            }
+        }
+        HeaderValue::from_str(&format!("{}=\"{url}\"", self.group))
+            .map_err(|err| error(&self.url, &err.to_string()))
+    }
+
+    /// Sets the policy's `report-to` directive to this group
+    pub fn apply_to(&self, mut builder: CspHeaderBuilder) -> CspHeaderBuilder {
+        builder
+            .directive_map
+            .insert(CspDirectiveType::ReportTo, vec![self.value()]);
+        builder
+    }
+}
+
+impl CspHeaderBuilder {
+    /// Clones the policy for a report-only trial run, adding `'report-sample'` to the directives
+    /// which govern scripts and styles so violation reports include a snippet of the code.
+    ///
+    /// Send the result as `Content-Security-Policy-Report-Only`
+    /// ([axum::http::header::CONTENT_SECURITY_POLICY_REPORT_ONLY]) alongside the enforced policy.
+    pub fn as_report_only_with_samples(&self) -> CspHeaderBuilder {
+        let mut targets: Vec<CspDirectiveType> = [
+            CspDirectiveType::ScriptSource,
+            CspDirectiveType::ScriptSourceElem,
+            CspDirectiveType::ScriptSourceAttr,
+            CspDirectiveType::StyleSource,
+            CspDirectiveType::StyleSourceElem,
+            CspDirectiveType::StyleSourceAttr,
+        ]
+        .into_iter()
+        .filter_map(|directive| self.effective_values(directive).map(|(d, _)| d))
+        .collect();
+        targets.sort();
+        targets.dedup();
+
+        targets
+            .into_iter()
+            .fold(self.clone(), |builder, directive| {
+                builder.add(directive, vec![CspValue::ReportSample])
+            })
+    }
+
+    /// Adds a `report-to` group pointing at the same URL as the existing `report-uri`, so older
+    /// and newer browsers both report. Returns the `Reporting-Endpoints` header value to send
+    /// with it, or `None` (and the policy unchanged) if there's no `report-uri`. Fails if the
+    /// group name or URL can't go in the header, see [ReportingConfig::endpoints_header_value].
+    ///
+    /// A reporting group only has one URL, so if `report-uri` lists several the first is used.
+    pub fn add_report_to_from_report_uri(
+        self,
+        group_name: &str,
+    ) -> Result<(Self, Option<HeaderValue>), RenderError> {
+        let url = self
+            .directive_map
+            .get(&CspDirectiveType::ReportUri)
+            .and_then(|values| values.first())
+            .map(|value| String::from(value.to_owned()));
+        match url {
+            Some(url) => {
+                let reporting = ReportingConfig::new(group_name, url);
+                let header = reporting.endpoints_header_value()?;
+                Ok((reporting.apply_to(self), Some(header)))
+            }
+            None => Ok((self, None)),
+        }
+    }
+}
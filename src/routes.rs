@@ -0,0 +1,97 @@
+//! Building [CspUrlMatcher]s from axum route templates
+
+use crate::{CspDirective, CspUrlMatcher};
+use regex::RegexSet;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A route template which couldn't be turned into a matcher
+pub struct GlobError {
+    pub template: String,
+    pub reason: String,
+}
+
+impl Display for GlobError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid route template {:?}: {}",
+            self.template, self.reason
+        )
+    }
+}
+
+impl std::error::Error for GlobError {}
+
+/// Turns `/users/{id}/files/{*path}` into an anchored regex, also accepting the older
+/// `/users/:id/files/*path` syntax
+fn template_to_pattern(template: &str) -> Result<String, GlobError> {
+    let error = |reason: &str| GlobError {
+        template: template.to_string(),
+        reason: reason.to_string(),
+    };
+    if !template.starts_with('/') {
+        return Err(error("routes must start with '/'"));
+    }
+
+    let segments: Vec<&str> = template[1..].split('/').collect();
+    let mut pattern = String::from("^");
+    for (index, segment) in segments.iter().enumerate() {
+        pattern.push('/');
+        let param = segment
+            .strip_prefix('{')
+            .map(|s| s.strip_suffix('}').ok_or_else(|| error("unclosed '{'")))
+            .transpose()?
+            .or_else(|| segment.strip_prefix(':'))
+            .or_else(|| segment.strip_prefix('*').map(|_| *segment));
+        match param {
+            Some(name) => {
+                let wildcard = name.strip_prefix('*');
+                if wildcard.unwrap_or(name).is_empty() {
+                    return Err(error("parameters need a name"));
+                }
+                if wildcard.is_some() {
+                    if index != segments.len() - 1 {
+                        return Err(error("wildcards can only be the last segment"));
+                    }
+                    pattern.push_str(".+");
+                } else {
+                    pattern.push_str("[^/]+");
+                }
+            }
+            None if segment.contains(['{', '}']) => {
+                return Err(error("parameters must be a whole segment"));
+            }
+            None => pattern.push_str(&regex::escape(segment)),
+        }
+    }
+    pattern.push('$');
+    Ok(pattern)
+}
+
+impl CspUrlMatcher {
+    /// Builds a matcher per route template, with its policy, so the CSP config follows the same
+    /// routes as the router. `{param}` matches one path segment and `{*rest}` the rest of the
+    /// path, as in axum.
+    ///
+    /// Matchers are returned in the same order, and the first match wins in
+    /// [crate::middleware::csp_matcher_middleware], so put a catch-all default (`/{*path}`) last.
+    pub fn from_routes(
+        routes: &[(&str, Vec<CspDirective>)],
+    ) -> Result<Vec<CspUrlMatcher>, GlobError> {
+        routes
+            .iter()
+            .map(|(template, directives)| {
+                let matcher =
+                    RegexSet::new([template_to_pattern(template)?]).map_err(|e| GlobError {
+                        template: template.to_string(),
+                        reason: e.to_string(),
+                    })?;
+                Ok(CspUrlMatcher {
+                    matcher,
+                    directives: directives.to_owned(),
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,109 @@
+//! Typed tokens for the `sandbox` directive
+
+use crate::{CspValue, ParseError};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The restrictions a `sandbox` directive can lift, see
+/// [MDN](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Security-Policy/sandbox).
+/// A `sandbox` directive without any tokens applies every restriction.
+///
+/// Converting a token into a [CspValue] gives the bare token, for adding to
+/// [CspDirectiveType::Sandbox](crate::CspDirectiveType::Sandbox).
+pub enum SandboxToken {
+    AllowDownloads,
+    AllowForms,
+    AllowModals,
+    AllowOrientationLock,
+    AllowPointerLock,
+    AllowPopups,
+    AllowPopupsToEscapeSandbox,
+    AllowPresentation,
+    /// Combined with [SandboxToken::AllowScripts], the content can remove its own sandbox
+    AllowSameOrigin,
+    AllowScripts,
+    AllowStorageAccessByUserActivation,
+    AllowTopNavigation,
+    AllowTopNavigationByUserActivation,
+    AllowTopNavigationToCustomProtocols,
+}
+
+impl SandboxToken {
+    /// Every token, in sort order
+    pub const ALL: &'static [SandboxToken] = &[
+        SandboxToken::AllowDownloads,
+        SandboxToken::AllowForms,
+        SandboxToken::AllowModals,
+        SandboxToken::AllowOrientationLock,
+        SandboxToken::AllowPointerLock,
+        SandboxToken::AllowPopups,
+        SandboxToken::AllowPopupsToEscapeSandbox,
+        SandboxToken::AllowPresentation,
+        SandboxToken::AllowSameOrigin,
+        SandboxToken::AllowScripts,
+        SandboxToken::AllowStorageAccessByUserActivation,
+        SandboxToken::AllowTopNavigation,
+        SandboxToken::AllowTopNavigationByUserActivation,
+        SandboxToken::AllowTopNavigationToCustomProtocols,
+    ];
+}
+
+impl AsRef<str> for SandboxToken {
+    fn as_ref(&self) -> &str {
+        match self {
+            SandboxToken::AllowDownloads => "allow-downloads",
+            SandboxToken::AllowForms => "allow-forms",
+            SandboxToken::AllowModals => "allow-modals",
+            SandboxToken::AllowOrientationLock => "allow-orientation-lock",
+            SandboxToken::AllowPointerLock => "allow-pointer-lock",
+            SandboxToken::AllowPopups => "allow-popups",
+            SandboxToken::AllowPopupsToEscapeSandbox => "allow-popups-to-escape-sandbox",
+            SandboxToken::AllowPresentation => "allow-presentation",
+            SandboxToken::AllowSameOrigin => "allow-same-origin",
+            SandboxToken::AllowScripts => "allow-scripts",
+            SandboxToken::AllowStorageAccessByUserActivation => {
+                "allow-storage-access-by-user-activation"
+            }
+            SandboxToken::AllowTopNavigation => "allow-top-navigation",
+            SandboxToken::AllowTopNavigationByUserActivation => {
+                "allow-top-navigation-by-user-activation"
+            }
+            SandboxToken::AllowTopNavigationToCustomProtocols => {
+                "allow-top-navigation-to-custom-protocols"
+            }
+        }
+    }
+}
+
+impl Display for SandboxToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+impl FromStr for SandboxToken {
+    type Err = ParseError;
+
+    /// Tokens are matched case-insensitively, as browsers do
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        SandboxToken::ALL
+            .iter()
+            .find(|t| t.as_ref() == name)
+            .copied()
+            .ok_or_else(|| ParseError::InvalidValue {
+                value: s.to_string(),
+                reason: "unknown sandbox token".to_string(),
+            })
+    }
+}
+
+impl From<SandboxToken> for CspValue {
+    fn from(token: SandboxToken) -> Self {
+        CspValue::Host {
+            value: token.to_string(),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Checking a policy against an organisation-wide allowlist of directives and values
+
+use crate::{ConfigError, CspDirectiveType, CspHeaderBuilder, CspValue, ParseError};
+use regex::Regex;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Default)]
+/// Which directives a policy may use and which values each may contain, for enforcing CSP
+/// standards with [CspHeaderBuilder::validate_against_schema]. Nothing is allowed until it's
+/// listed.
+pub struct CspSchema {
+    /// Each value has to match one of its directive's patterns in full
+    pub directives: HashMap<CspDirectiveType, Vec<Regex>>,
+}
+
+fn compile_pattern(pattern: &str) -> Result<Regex, ParseError> {
+    Regex::new(&format!("^(?:{pattern})$")).map_err(|err| ParseError::InvalidValue {
+        value: pattern.to_string(),
+        reason: err.to_string(),
+    })
+}
+
+impl CspSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows a directive, with values matching any of these regular expressions, eg.
+    /// `'self'` or `https://[a-z]+\.example\.com`. Patterns match the whole rendered value.
+    pub fn allow(
+        mut self,
+        directive: CspDirectiveType,
+        patterns: &[&str],
+    ) -> Result<Self, ParseError> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| compile_pattern(pattern))
+            .collect::<Result<Vec<Regex>, ParseError>>()?;
+        self.directives
+            .entry(directive)
+            .or_default()
+            .extend(patterns);
+        Ok(self)
+    }
+
+    /// Builds a schema from configuration shaped like `{"script-src": ["'self'", "https://.*"]}`.
+    ///
+    /// Every bad directive name and pattern is reported, not just the first one.
+    pub fn try_from_map(map: HashMap<String, Vec<String>>) -> Result<Self, ConfigError> {
+        let mut entries: Vec<(String, Vec<String>)> = map.into_iter().collect();
+        entries.sort();
+
+        let mut schema = CspSchema::new();
+        let mut errors = vec![];
+        for (name, raw_patterns) in entries {
+            let directive = CspDirectiveType::from_str(&name).map_err(|e| errors.push(e));
+            let patterns: Vec<Regex> = raw_patterns
+                .iter()
+                .filter_map(|p| compile_pattern(p).map_err(|e| errors.push(e)).ok())
+                .collect();
+            if let Ok(directive) = directive {
+                schema
+                    .directives
+                    .entry(directive)
+                    .or_default()
+                    .extend(patterns);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(schema)
+        } else {
+            Err(ConfigError { errors })
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Something in a policy which its [CspSchema] doesn't allow
+pub struct SchemaViolation {
+    /// `None` for unknown directives, which schemas can't list
+    pub directive: Option<CspDirectiveType>,
+    /// `None` when the whole directive isn't allowed
+    pub value: Option<CspValue>,
+    pub reason: String,
+}
+
+impl CspHeaderBuilder {
+    /// Checks every directive and value in the policy is allowed by `schema`, eg. in CI. Every
+    /// violation is returned, in directive order.
+    pub fn validate_against_schema(&self, schema: &CspSchema) -> Result<(), Vec<SchemaViolation>> {
+        let mut directives = self.directive_map.iter().collect::<Vec<_>>();
+        directives.sort_by_key(|(directive, _)| **directive);
+
+        let mut violations = vec![];
+        for (directive, values) in directives {
+            let Some(patterns) = schema.directives.get(directive) else {
+                violations.push(SchemaViolation {
+                    directive: Some(*directive),
+                    value: None,
+                    reason: format!("{directive} isn't allowed by the schema"),
+                });
+                continue;
+            };
+            let mut values = values.clone();
+            values.sort();
+            values.dedup();
+            for value in values {
+                let rendered = String::from(value.clone());
+                if !patterns.iter().any(|pattern| pattern.is_match(&rendered)) {
+                    violations.push(SchemaViolation {
+                        directive: Some(*directive),
+                        value: Some(value),
+                        reason: format!("{rendered} isn't allowed in {directive} by the schema"),
+                    });
+                }
+            }
+        }
+
+        let mut unknown = self.unknown_directives.keys().collect::<Vec<_>>();
+        unknown.sort();
+        violations.extend(unknown.into_iter().map(|name| SchemaViolation {
+            directive: None,
+            value: None,
+            reason: format!("unknown directive {name} isn't allowed by the schema"),
+        }));
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
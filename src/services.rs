@@ -0,0 +1,164 @@
+//! Policy entries that well-known third-party services document as required
+
+use crate::{CspDiff, CspDirectiveType, CspHeaderBuilder, CspValue};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+/// A third-party service with documented CSP requirements.
+///
+/// These follow each vendor's published guidance, check it if you use optional features (eg.
+/// Stripe's Google Maps address autocomplete) that need more.
+pub enum KnownService {
+    /// [Stripe.js](https://docs.stripe.com/security/guide#content-security-policy)
+    Stripe,
+    /// [Google Analytics 4](https://developers.google.com/tag-platform/security/guides/csp)
+    GoogleAnalytics,
+    /// [Sentry browser SDK](https://docs.sentry.io/platforms/javascript/install/loader/#content-security-policy)
+    Sentry,
+    /// [Intercom Messenger](https://www.intercom.com/help/en/articles/3894-using-intercom-with-content-security-policy)
+    Intercom,
+}
+
+impl KnownService {
+    fn entries(&self) -> &'static [(CspDirectiveType, &'static [&'static str])] {
+        match self {
+            KnownService::Stripe => &[
+                (CspDirectiveType::ConnectSrc, &["https://api.stripe.com"]),
+                (
+                    CspDirectiveType::FrameSrc,
+                    &["https://js.stripe.com", "https://hooks.stripe.com"],
+                ),
+                (CspDirectiveType::ScriptSource, &["https://js.stripe.com"]),
+            ],
+            KnownService::GoogleAnalytics => &[
+                (
+                    CspDirectiveType::ConnectSrc,
+                    &[
+                        "https://*.google-analytics.com",
+                        "https://*.analytics.google.com",
+                        "https://*.googletagmanager.com",
+                    ],
+                ),
+                (
+                    CspDirectiveType::ImgSrc,
+                    &[
+                        "https://*.google-analytics.com",
+                        "https://*.googletagmanager.com",
+                    ],
+                ),
+                (
+                    CspDirectiveType::ScriptSource,
+                    &["https://*.googletagmanager.com"],
+                ),
+            ],
+            KnownService::Sentry => &[
+                (
+                    CspDirectiveType::ConnectSrc,
+                    &["https://*.ingest.sentry.io"],
+                ),
+                (
+                    CspDirectiveType::ScriptSource,
+                    &[
+                        "https://browser.sentry-cdn.com",
+                        "https://js.sentry-cdn.com",
+                    ],
+                ),
+            ],
+            KnownService::Intercom => &[
+                (
+                    CspDirectiveType::ConnectSrc,
+                    &[
+                        "https://via.intercom.io",
+                        "https://api.intercom.io",
+                        "https://api-iam.intercom.io",
+                        "https://api-ping.intercom.io",
+                        "https://*.intercom-messenger.com",
+                        "wss://*.intercom-messenger.com",
+                        "https://nexus-websocket-a.intercom.io",
+                        "wss://nexus-websocket-a.intercom.io",
+                        "https://nexus-websocket-b.intercom.io",
+                        "wss://nexus-websocket-b.intercom.io",
+                        "https://uploads.intercomcdn.com",
+                        "https://uploads.intercomusercontent.com",
+                    ],
+                ),
+                (
+                    CspDirectiveType::FontSrc,
+                    &[
+                        "https://js.intercomcdn.com",
+                        "https://fonts.intercomcdn.com",
+                    ],
+                ),
+                (
+                    CspDirectiveType::FrameSrc,
+                    &[
+                        "https://intercom-sheets.com",
+                        "https://www.intercom-reporting.com",
+                    ],
+                ),
+                (
+                    CspDirectiveType::ImgSrc,
+                    &[
+                        "blob:",
+                        "data:",
+                        "https://js.intercomcdn.com",
+                        "https://static.intercomassets.com",
+                        "https://downloads.intercomcdn.com",
+                        "https://uploads.intercomusercontent.com",
+                        "https://gifs.intercomcdn.com",
+                    ],
+                ),
+                (CspDirectiveType::MediaSrc, &["https://js.intercomcdn.com"]),
+                (
+                    CspDirectiveType::ScriptSource,
+                    &[
+                        "https://app.intercom.io",
+                        "https://widget.intercom.io",
+                        "https://js.intercomcdn.com",
+                    ],
+                ),
+                (CspDirectiveType::StyleSource, &["'unsafe-inline'"]),
+            ],
+        }
+    }
+}
+
+impl KnownService {
+    /// The values this service needs, by directive, in directive order. This is exactly what
+    /// [CspHeaderBuilder::with_service] adds.
+    pub fn required_additions(&self) -> Vec<(CspDirectiveType, Vec<CspValue>)> {
+        self.entries()
+            .iter()
+            .map(|(directive, values)| {
+                (
+                    *directive,
+                    values
+                        .iter()
+                        .map(|v| CspValue::from_str(v).expect("known service entries are valid"))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    /// What adding this service would change in `builder`, without changing anything, to assess
+    /// the impact before adding it. Values the policy already has aren't listed.
+    ///
+    /// Watch for added directives: one which was falling back to `default-src` stops doing so, and
+    /// only allows what's added.
+    pub fn preview(&self, builder: &CspHeaderBuilder) -> CspDiff {
+        builder.diff(&builder.clone().with_service(*self))
+    }
+}
+
+impl CspHeaderBuilder {
+    /// Adds the entries a third-party service needs to work
+    pub fn with_service(self, service: KnownService) -> Self {
+        service
+            .required_additions()
+            .into_iter()
+            .fold(self, |builder, (directive, values)| {
+                builder.add(directive, values)
+            })
+    }
+}
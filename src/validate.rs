@@ -0,0 +1,430 @@
+//! Linting policies for common mistakes
+
+use crate::{CspDirectiveType, CspHeaderBuilder, CspValue};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// How much a finding from [CspHeaderBuilder::validate] matters
+pub enum Severity {
+    /// Something to be aware of
+    Info,
+    /// Probably a mistake
+    Warning,
+    /// A serious gap in the protection the policy provides
+    Error,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A finding from [CspHeaderBuilder::validate]
+pub struct CspWarning {
+    pub severity: Severity,
+    /// The directive the finding is about, if it's about a specific one
+    pub directive: Option<CspDirectiveType>,
+    pub message: String,
+}
+
+impl CspWarning {
+    pub fn new(
+        severity: Severity,
+        directive: Option<CspDirectiveType>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity,
+            directive,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for CspWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.directive {
+            Some(directive) => write!(f, "{:?} {}: {}", self.severity, directive, self.message),
+            None => write!(f, "{:?}: {}", self.severity, self.message),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// Optional checks for [CspHeaderBuilder::validate_with_options]
+pub struct ValidationOptions {
+    /// Warn when any directive has more values than this
+    pub max_values: Option<usize>,
+    /// Warn when a specific directive has more values than this, overrides `max_values`
+    pub max_values_per_directive: HashMap<CspDirectiveType, usize>,
+}
+
+impl ValidationOptions {
+    pub fn with_max_values(mut self, max_values: usize) -> Self {
+        self.max_values = Some(max_values);
+        self
+    }
+
+    pub fn with_directive_max_values(
+        mut self,
+        directive: CspDirectiveType,
+        max_values: usize,
+    ) -> Self {
+        self.max_values_per_directive.insert(directive, max_values);
+        self
+    }
+}
+
+/// A custom lint for [CspHeaderBuilder::validate_with], for rules specific to an organisation
+/// like "every host must be on example.com".
+pub trait CspValidator {
+    /// Checks the policy, returning any findings
+    fn validate(&self, builder: &CspHeaderBuilder) -> Vec<CspWarning>;
+}
+
+impl CspHeaderBuilder {
+    /// Checks the policy for common mistakes, most severe findings first.
+    pub fn validate(&self) -> Vec<CspWarning> {
+        self.validate_with_options(&ValidationOptions::default())
+    }
+
+    /// Checks a policy which will be reused across requests doesn't contain any nonces, which
+    /// must be unique per response. Use [CspValue::NoncePlaceholder] in shared policies instead.
+    pub fn assert_cacheable(&self) -> Result<(), Vec<CspWarning>> {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .collect::<Vec<&CspDirectiveType>>();
+        keys.sort();
+        let res: Vec<CspWarning> = keys
+            .into_iter()
+            .filter(|d| {
+                self.directive_map[*d]
+                    .iter()
+                    .any(|v| matches!(v, CspValue::Nonce { .. }))
+            })
+            .map(|d| {
+                CspWarning::new(
+                    Severity::Error,
+                    Some(*d),
+                    "contains a nonce, which must not be reused in a cached policy",
+                )
+            })
+            .collect();
+        if res.is_empty() {
+            Ok(())
+        } else {
+            Err(res)
+        }
+    }
+
+    /// Checks no development-only relaxations, like those added with
+    /// [CspHeaderBuilder::with_dev_overrides], are left in a production policy: `'unsafe-eval'`,
+    /// and sources on `localhost`, `127.0.0.1` or `[::1]` such as a hot reload websocket.
+    pub fn assert_production_safe(&self) -> Result<(), Vec<CspWarning>> {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .collect::<Vec<&CspDirectiveType>>();
+        keys.sort();
+        let mut res = vec![];
+        for directive in keys {
+            let mut values = self.directive_map[directive].clone();
+            values.sort();
+            values.dedup();
+            for value in values {
+                let reason = match &value {
+                    CspValue::UnsafeEval => "'unsafe-eval' is a development-only relaxation",
+                    CspValue::Host { value } if is_loopback_host(value) => {
+                        "allows a local development server"
+                    }
+                    _ => continue,
+                };
+                res.push(CspWarning::new(
+                    Severity::Error,
+                    Some(*directive),
+                    format!("{}: {reason}", String::from(value)),
+                ));
+            }
+        }
+        if res.is_empty() {
+            Ok(())
+        } else {
+            Err(res)
+        }
+    }
+
+    /// Checks an integrity-only policy, like [CspHeaderBuilder::hash_only], still only runs scripts
+    /// and styles with a nonce or hash: no hosts (including `*`), schemes, `'self'` or
+    /// `'unsafe-*'` keywords in the script and style directives, or the `default-src` they fall
+    /// back to. Every problem is reported, including each directive with nothing to fall back to.
+    pub fn assert_integrity_only(&self) -> Result<(), Vec<CspWarning>> {
+        let mut res = vec![];
+        let mut directives = std::collections::BTreeSet::new();
+        for directive in [
+            CspDirectiveType::ScriptSource,
+            CspDirectiveType::ScriptSourceAttr,
+            CspDirectiveType::ScriptSourceElem,
+            CspDirectiveType::StyleSource,
+            CspDirectiveType::StyleSourceAttr,
+            CspDirectiveType::StyleSourceElem,
+        ] {
+            match self.effective_values(directive) {
+                Some((source, _)) => {
+                    directives.insert(source);
+                }
+                None => res.push(CspWarning::new(
+                    Severity::Error,
+                    Some(directive),
+                    "isn't restricted, there's no default-src to fall back to",
+                )),
+            }
+        }
+
+        for directive in directives {
+            let mut values = self.directive_map[&directive].clone();
+            values.sort();
+            values.dedup();
+            for value in values {
+                let weakening = match value {
+                    CspValue::SelfSite => "'self' allows any script or style from the same origin",
+                    CspValue::UnsafeInline
+                    | CspValue::UnsafeEval
+                    | CspValue::UnsafeHashes
+                    | CspValue::WasmUnsafeEval => "'unsafe-*' keywords weaken integrity checks",
+                    ref value if value.is_host_or_scheme() => {
+                        "host and scheme allowlists bypass nonces and hashes"
+                    }
+                    _ => continue,
+                };
+                res.push(CspWarning::new(
+                    Severity::Error,
+                    Some(directive),
+                    format!("{}: {weakening}", String::from(value)),
+                ));
+            }
+        }
+        if res.is_empty() {
+            Ok(())
+        } else {
+            Err(res)
+        }
+    }
+
+    /// [CspHeaderBuilder::validate], with extra configurable checks
+    pub fn validate_with_options(&self, options: &ValidationOptions) -> Vec<CspWarning> {
+        let mut res = vec![];
+        self.check_value_counts(options, &mut res);
+        self.check_inline_scripts(&mut res);
+        self.check_deprecated_directives(&mut res);
+        self.check_missing_replacements(&mut res);
+        self.check_misplaced_keywords(&mut res);
+        self.check_broad_connect(&mut res);
+        self.check_report_sample(&mut res);
+        self.check_inline_styles_with_nonce_scripts(&mut res);
+        res.extend(self.input_warnings.iter().cloned());
+        res.sort_by_key(|w| std::cmp::Reverse(w.severity));
+        res
+    }
+
+    /// [CspHeaderBuilder::validate], plus the findings from each custom validator, most severe
+    /// first.
+    pub fn validate_with(&self, validators: &[Box<dyn CspValidator>]) -> Vec<CspWarning> {
+        let mut res = self.validate();
+        for validator in validators {
+            res.extend(validator.validate(self));
+        }
+        res.sort_by_key(|w| std::cmp::Reverse(w.severity));
+        res
+    }
+
+    /// `'unsafe-inline'` without a nonce, hash or `'strict-dynamic'` means any injected script runs
+    fn check_inline_scripts(&self, res: &mut Vec<CspWarning>) {
+        if let Some((directive, values)) = self.effective_values(CspDirectiveType::ScriptSource) {
+            if values.contains(&CspValue::UnsafeInline)
+                && !values
+                    .iter()
+                    .any(|v| v.is_nonce_or_hash() || v == &CspValue::StrictDynamic)
+            {
+                res.push(CspWarning::new(
+                    Severity::Error,
+                    Some(directive),
+                    "'unsafe-inline' allows inline scripts without a nonce, hash or 'strict-dynamic', which provides almost no XSS protection",
+                ));
+            }
+        }
+    }
+
+    fn check_deprecated_directives(&self, res: &mut Vec<CspWarning>) {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .filter(|d| d.is_deprecated())
+            .collect::<Vec<&CspDirectiveType>>();
+        keys.sort();
+        for directive in keys {
+            let advice = match directive {
+                CspDirectiveType::PrefetchSrc => {
+                    "browsers no longer support it, use default-src or connect-src instead"
+                }
+                CspDirectiveType::ReportUri => "use report-to as well, for newer browsers",
+                CspDirectiveType::BlockAllMixedContent => "use upgrade-insecure-requests instead",
+                _ => "browsers no longer support it",
+            };
+            res.push(CspWarning::new(
+                Severity::Warning,
+                Some(*directive),
+                format!("{directive} is deprecated, {advice}"),
+            ));
+        }
+    }
+
+    /// One summary of the deprecated directives whose modern replacement isn't set, since those
+    /// parts of the policy stop working once browsers drop them
+    fn check_missing_replacements(&self, res: &mut Vec<CspWarning>) {
+        let mut missing = self
+            .directive_map
+            .keys()
+            .filter_map(|d| {
+                d.modern_replacement()
+                    .filter(|r| !self.directive_map.contains_key(r))
+                    .map(|r| (*d, r))
+            })
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return;
+        }
+        missing.sort();
+        res.push(CspWarning::new(
+            Severity::Warning,
+            None,
+            format!(
+                "policy relies on deprecated directives without their replacements, add {}",
+                missing
+                    .iter()
+                    .map(|(deprecated, replacement)| format!("{replacement} (for {deprecated})"))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        ));
+    }
+
+    fn check_value_counts(&self, options: &ValidationOptions, res: &mut Vec<CspWarning>) {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .collect::<Vec<&CspDirectiveType>>();
+        keys.sort();
+        for directive in keys {
+            let limit = options
+                .max_values_per_directive
+                .get(directive)
+                .copied()
+                .or(options.max_values);
+            let count = self.directive_map[directive].len();
+            if let Some(limit) = limit.filter(|limit| count > *limit) {
+                res.push(CspWarning::new(
+                    Severity::Warning,
+                    Some(*directive),
+                    format!("has {count} values, more than the configured maximum of {limit}"),
+                ));
+            }
+        }
+    }
+
+    /// Keywords which do nothing in the directive they're in, eg. `'strict-dynamic'` in `img-src`
+    fn check_misplaced_keywords(&self, res: &mut Vec<CspWarning>) {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .filter(|d| d.takes_source_list())
+            .collect::<Vec<&CspDirectiveType>>();
+        keys.sort();
+        for directive in keys {
+            let mut values = self.directive_map[directive].clone();
+            values.sort();
+            for keyword in values.iter().filter_map(|v| v.restricted_keyword()) {
+                if !directive.allowed_keywords().contains(&keyword) {
+                    res.push(CspWarning::new(
+                        Severity::Warning,
+                        Some(*directive),
+                        format!("{keyword} has no effect in {directive}"),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// `connect-src *` or `https:` lets injected code send data anywhere, even when scripts are
+    /// locked down
+    fn check_broad_connect(&self, res: &mut Vec<CspWarning>) {
+        if let Some((directive, values)) = self.effective_values(CspDirectiveType::ConnectSrc) {
+            if let Some(broad) = values.iter().find(|v| {
+                v.is_wildcard() || matches!(v, CspValue::SchemeHttps | CspValue::SchemeHttp)
+            }) {
+                res.push(CspWarning::new(
+                    Severity::Warning,
+                    Some(directive),
+                    format!(
+                        "{} lets scripts connect to any host, which allows data exfiltration",
+                        String::from(broad.to_owned())
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// `'report-sample'` puts the start of blocked code in reports, which might be sensitive
+    fn check_report_sample(&self, res: &mut Vec<CspWarning>) {
+        let mut keys = self
+            .directive_map
+            .keys()
+            .filter(|d| self.directive_map[*d].contains(&CspValue::ReportSample))
+            .collect::<Vec<&CspDirectiveType>>();
+        keys.sort();
+        for directive in keys {
+            res.push(CspWarning::new(
+                Severity::Info,
+                Some(*directive),
+                "'report-sample' sends the first 40 characters of blocked code to the report endpoint, which may disclose sensitive inline content",
+            ));
+        }
+    }
+
+    /// Nonce-based scripts with `'unsafe-inline'` styles is inconsistent: styles are left weaker
+    /// than scripts, and frameworks which inject `<style>` tags need the nonce to keep working
+    /// once `'unsafe-inline'` goes
+    fn check_inline_styles_with_nonce_scripts(&self, res: &mut Vec<CspWarning>) {
+        let nonce_scripts = self
+            .effective_values(CspDirectiveType::ScriptSource)
+            .is_some_and(|(_, values)| {
+                values
+                    .iter()
+                    .any(|v| v.is_nonce_or_hash() || v == &CspValue::StrictDynamic)
+            });
+        if !nonce_scripts {
+            return;
+        }
+        if let Some((directive, values)) = self.effective_values(CspDirectiveType::StyleSource) {
+            if values.contains(&CspValue::UnsafeInline)
+                && !values.iter().any(|v| v.is_nonce_or_hash())
+            {
+                res.push(CspWarning::new(
+                    Severity::Info,
+                    Some(directive),
+                    "scripts use a nonce but styles rely on 'unsafe-inline', add the nonce to style-src for frameworks which inject <style> tags",
+                ));
+            }
+        }
+    }
+}
+
+/// Does a host source point at the local machine, eg. `ws://localhost:3000`?
+fn is_loopback_host(source: &str) -> bool {
+    let host = source.split_once("://").map_or(source, |(_, rest)| rest);
+    let host = host.split('/').next().unwrap_or(host);
+    let host = match host.strip_prefix('[') {
+        // IPv6 literals have colons in them, so take the part in brackets
+        Some(v6) => v6.split(']').next().unwrap_or(v6),
+        None => host.split(':').next().unwrap_or(host),
+    };
+    let host = host.to_ascii_lowercase();
+    host == "localhost" || host.ends_with(".localhost") || host == "::1" || host.starts_with("127.")
+}
@@ -0,0 +1,179 @@
+//! Reloading a policy from a file while the server is running, behind the `watch` feature
+
+use crate::{CspHeaderBuilder, ParseError};
+use arc_swap::ArcSwap;
+use axum::extract::{Request, State};
+use axum::http::header::CONTENT_SECURITY_POLICY;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::fmt::{Debug, Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Debug)]
+/// Something went wrong loading or watching a policy file
+pub enum WatchError {
+    Io(std::io::Error),
+    /// The file was empty, which usually means it was read mid-write
+    Empty,
+    Parse(ParseError),
+    Notify(notify::Error),
+}
+
+impl Display for WatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchError::Io(e) => write!(f, "failed to read policy file: {e}"),
+            WatchError::Empty => write!(f, "policy file is empty"),
+            WatchError::Parse(e) => write!(f, "failed to parse policy file: {e}"),
+            WatchError::Notify(e) => write!(f, "failed to watch policy file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for WatchError {}
+
+/// A policy alongside its rendered header, so requests don't have to render it
+struct LoadedPolicy {
+    builder: Arc<CspHeaderBuilder>,
+    header: HeaderValue,
+}
+
+#[derive(Clone)]
+/// A shared, swappable policy, cheap to clone and read from middleware. The policy is rendered
+/// when it's stored, not per request.
+pub struct CspPolicyHandle(Arc<ArcSwap<LoadedPolicy>>);
+
+impl Debug for CspPolicyHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CspPolicyHandle")
+            .field(&self.load())
+            .finish()
+    }
+}
+
+impl LoadedPolicy {
+    fn new(builder: CspHeaderBuilder) -> Self {
+        let header = builder.clone().finish();
+        Self {
+            builder: Arc::new(builder),
+            header,
+        }
+    }
+}
+
+impl CspPolicyHandle {
+    pub fn new(builder: CspHeaderBuilder) -> Self {
+        Self(Arc::new(ArcSwap::from_pointee(LoadedPolicy::new(builder))))
+    }
+
+    /// The current policy
+    pub fn load(&self) -> Arc<CspHeaderBuilder> {
+        self.0.load().builder.clone()
+    }
+
+    /// The current policy's rendered header
+    pub fn header(&self) -> HeaderValue {
+        self.0.load().header.clone()
+    }
+
+    /// Replace the current policy
+    pub fn store(&self, builder: CspHeaderBuilder) {
+        self.0.store(Arc::new(LoadedPolicy::new(builder)));
+    }
+}
+
+/// Reads and parses a policy file, which holds a header value (newlines are fine)
+fn load_policy(path: &Path) -> Result<CspHeaderBuilder, WatchError> {
+    let contents = std::fs::read_to_string(path).map_err(WatchError::Io)?;
+    if contents.trim().is_empty() {
+        return Err(WatchError::Empty);
+    }
+    CspHeaderBuilder::from_header_str(&contents).map_err(WatchError::Parse)
+}
+
+/// Watches a policy file and swaps the policy in its [CspPolicyHandle] when the file changes.
+///
+/// If a changed file doesn't parse, the error is logged and the previous policy is kept.
+/// Watching stops when this is dropped.
+pub struct CspConfigWatcher {
+    path: PathBuf,
+    handle: CspPolicyHandle,
+    _watcher: RecommendedWatcher,
+}
+
+impl Debug for CspConfigWatcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CspConfigWatcher")
+            .field("path", &self.path)
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CspConfigWatcher {
+    /// Loads the policy file, failing if it's not valid, and starts watching it.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, WatchError> {
+        let path = std::path::absolute(path.as_ref()).map_err(WatchError::Io)?;
+        let handle = CspPolicyHandle::new(load_policy(&path)?);
+
+        let watched_path = path.clone();
+        let watched_handle = handle.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if event.paths.iter().any(|p| p == &watched_path) => {
+                    if event.kind.is_remove() {
+                        return;
+                    }
+                    match load_policy(&watched_path) {
+                        Ok(builder) => watched_handle.store(builder),
+                        Err(err) => log::warn!(
+                            "Keeping previous CSP policy, reloading {} failed: {err}",
+                            watched_path.display()
+                        ),
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => log::warn!("Error watching CSP policy file: {err}"),
+            })
+            .map_err(WatchError::Notify)?;
+        // watch the directory, so editors which replace the file rather than writing to it still work
+        let directory = path.parent().unwrap_or(Path::new("/"));
+        watcher
+            .watch(directory, RecursiveMode::NonRecursive)
+            .map_err(WatchError::Notify)?;
+
+        Ok(Self {
+            path,
+            handle,
+            _watcher: watcher,
+        })
+    }
+
+    /// A handle to the live policy, for [csp_watched_middleware]
+    pub fn handle(&self) -> CspPolicyHandle {
+        self.handle.clone()
+    }
+
+    /// Reload the file now, rather than waiting for a change notification
+    pub fn reload(&self) -> Result<(), WatchError> {
+        self.handle.store(load_policy(&self.path)?);
+        Ok(())
+    }
+}
+
+/// Adds the current policy from a [CspPolicyHandle] to every response
+pub async fn csp_watched_middleware(
+    State(handle): State<CspPolicyHandle>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let header = handle.header();
+    let mut response = next.run(req).await;
+    response
+        .headers_mut()
+        .insert(CONTENT_SECURITY_POLICY, header);
+    response
+}
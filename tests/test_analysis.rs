@@ -0,0 +1,260 @@
+use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue};
+
+#[test]
+fn test_ineffective_values_strict_dynamic() {
+    let builder = CspHeaderBuilder::new().add(
+        CspDirectiveType::ScriptSource,
+        vec![
+            CspValue::StrictDynamic,
+            CspValue::Host {
+                value: "https://cdn.example.com".to_string(),
+            },
+        ],
+    );
+    assert_eq!(
+        builder.ineffective_values(),
+        vec![(
+            CspDirectiveType::ScriptSource,
+            CspValue::Host {
+                value: "https://cdn.example.com".to_string()
+            }
+        )]
+    );
+}
+
+#[test]
+fn test_ineffective_values_none_and_nonce() {
+    let builder = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ObjectSrc,
+            vec![CspValue::None, CspValue::SelfSite],
+        )
+        .add(
+            CspDirectiveType::StyleSource,
+            vec![
+                CspValue::UnsafeInline,
+                CspValue::Nonce {
+                    value: "abc123".to_string(),
+                },
+            ],
+        )
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::None]);
+    assert_eq!(
+        builder.ineffective_values(),
+        vec![
+            (CspDirectiveType::ObjectSrc, CspValue::None),
+            (CspDirectiveType::StyleSource, CspValue::UnsafeInline),
+        ]
+    );
+}
+
+#[test]
+fn test_referenced_hosts() {
+    let cdn = CspValue::Host {
+        value: "https://cdn.example.com".to_string(),
+    };
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![CspValue::SelfSite, cdn.clone()],
+        )
+        .add(CspDirectiveType::ImgSrc, vec![cdn, CspValue::SchemeData])
+        .add(
+            CspDirectiveType::ConnectSrc,
+            vec![CspValue::Host {
+                value: "wss://ws.example.com".to_string(),
+            }],
+        )
+        .add(
+            CspDirectiveType::ReportTo,
+            vec![CspValue::Host {
+                value: "csp-endpoint".to_string(),
+            }],
+        );
+    assert_eq!(
+        builder.referenced_hosts(),
+        vec!["data:", "https://cdn.example.com", "wss://ws.example.com"]
+    );
+}
+
+#[test]
+fn test_recommended_frame_options() {
+    let frame_options = |values: Vec<CspValue>| {
+        CspHeaderBuilder::new()
+            .add(CspDirectiveType::FrameAncestors, values)
+            .recommended_frame_options()
+    };
+    assert_eq!(
+        frame_options(vec![CspValue::None]),
+        Some(axum::http::HeaderValue::from_static("DENY"))
+    );
+    assert_eq!(
+        frame_options(vec![CspValue::SelfSite]),
+        Some(axum::http::HeaderValue::from_static("SAMEORIGIN"))
+    );
+    assert_eq!(
+        frame_options(vec![
+            CspValue::SelfSite,
+            CspValue::Host {
+                value: "https://partner.example.com".to_string()
+            }
+        ]),
+        None
+    );
+    assert_eq!(CspHeaderBuilder::new().recommended_frame_options(), None);
+}
+
+#[test]
+fn test_is_effectively_unrestricted() {
+    use axum_csp::CspDirective;
+
+    let wildcard = CspValue::Host {
+        value: "*".to_string(),
+    };
+    assert!(
+        CspDirective::from(CspDirectiveType::ScriptSource, vec![wildcard])
+            .is_effectively_unrestricted()
+    );
+    assert!(CspDirective::from(
+        CspDirectiveType::ImgSrc,
+        vec![CspValue::SchemeHttp, CspValue::SchemeHttps]
+    )
+    .is_effectively_unrestricted());
+    assert!(
+        !CspDirective::from(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite])
+            .is_effectively_unrestricted()
+    );
+    assert!(
+        !CspDirective::from(CspDirectiveType::ImgSrc, vec![CspValue::SchemeHttps])
+            .is_effectively_unrestricted()
+    );
+
+    // 'unsafe-inline' lets injected scripts and styles run, unless a nonce or hash disables it
+    for directive in [
+        CspDirectiveType::ScriptSource,
+        CspDirectiveType::StyleSource,
+    ] {
+        assert!(
+            CspDirective::from(directive, vec![CspValue::SelfSite, CspValue::UnsafeInline])
+                .is_effectively_unrestricted()
+        );
+        assert!(!CspDirective::from(
+            directive,
+            vec![
+                CspValue::UnsafeInline,
+                CspValue::Sha256 {
+                    value: "abc".to_string()
+                }
+            ]
+        )
+        .is_effectively_unrestricted());
+    }
+    // and does nothing in directives which aren't about inline content
+    assert!(
+        !CspDirective::from(CspDirectiveType::ImgSrc, vec![CspValue::UnsafeInline])
+            .is_effectively_unrestricted()
+    );
+}
+
+#[test]
+fn test_allowed_schemes() {
+    let builder = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ImgSrc,
+            vec![CspValue::SchemeHttps, CspValue::SchemeData],
+        )
+        .add(
+            CspDirectiveType::ConnectSrc,
+            vec![
+                CspValue::Host {
+                    value: "http://legacy.example.com".to_string(),
+                },
+                CspValue::Host {
+                    value: "api.example.com".to_string(),
+                },
+                CspValue::SchemeOther {
+                    value: "Blob:".to_string(),
+                },
+            ],
+        );
+    assert_eq!(
+        builder.allowed_schemes().into_iter().collect::<Vec<_>>(),
+        vec!["blob", "data", "http", "https"]
+    );
+}
+
+#[test]
+fn test_browser_normalized() {
+    let builder = CspHeaderBuilder::from_header_str(
+        "SCRIPT-SRC 'self' 'strict-dynamic' https: 'nonce-abc' 'unsafe-inline'; script-src *; \
+         img-src 'none' data: data:; style-src 'nonce-abc' 'unsafe-inline'; object-src 'none'",
+    )
+    .expect("failed to parse");
+    assert_eq!(
+        builder.browser_normalized().finish_string(),
+        "img-src data:; object-src 'none'; script-src 'strict-dynamic' 'nonce-abc'; style-src 'nonce-abc'"
+    );
+}
+
+#[test]
+fn test_explain_with_origin() {
+    let builder = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![
+                CspValue::SelfSite,
+                CspValue::Host {
+                    value: "https://cdn.example.com".to_string(),
+                },
+            ],
+        )
+        .add(CspDirectiveType::ObjectSrc, vec![CspValue::None])
+        .add(
+            CspDirectiveType::ReportTo,
+            vec![CspValue::Host {
+                value: "csp".to_string(),
+            }],
+        );
+    assert_eq!(
+        builder.explain(Some("https://example.com")),
+        vec![
+            "object-src: nothing",
+            "report-to: csp",
+            "script-src: same origin (https://example.com), https://cdn.example.com",
+        ]
+    );
+    assert_eq!(
+        builder.explain(None)[2],
+        "script-src: same origin, https://cdn.example.com"
+    );
+
+    // an empty source list blocks everything, while flags like upgrade-insecure-requests are on
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ScriptSourceAttr, vec![])
+        .add(CspDirectiveType::UpgradeInsecureRequests, vec![]);
+    assert_eq!(
+        builder.explain(None),
+        vec![
+            "script-src-attr: nothing (blocked)",
+            "upgrade-insecure-requests: enabled",
+        ]
+    );
+}
+
+#[test]
+fn test_permissiveness_score() {
+    let weak = CspHeaderBuilder::from_header_str(
+        "default-src *; script-src 'self' 'unsafe-inline' 'unsafe-eval' http:; style-src 'unsafe-inline'",
+    )
+    .expect("should parse");
+    // 25 + 15 + 20 (default-src *) + 10 (http:) + 10 (connect-src) + 10 (frame-ancestors) + 5
+    assert_eq!(weak.permissiveness_score(), 95);
+
+    let strict = CspHeaderBuilder::api_only();
+    assert_eq!(strict.permissiveness_score(), 0);
+    assert!(weak.permissiveness_score() > strict.permissiveness_score());
+
+    // nothing set at all is missing object-src and frame-ancestors
+    assert_eq!(CspHeaderBuilder::new().permissiveness_score(), 20);
+}
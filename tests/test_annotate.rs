@@ -0,0 +1,72 @@
+use axum_csp::{CspAnnotation, CspDirectiveType, CspHeaderBuilder, CspValue};
+
+#[test]
+fn test_annotations_dont_affect_finish() {
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SchemeData])
+        .annotate(
+            CspDirectiveType::ImgSrc,
+            Some(CspValue::SchemeData),
+            "inline SVG icons",
+        );
+    assert_eq!(builder.annotations_for(CspDirectiveType::ImgSrc).len(), 1);
+    assert!(builder
+        .annotations_for(CspDirectiveType::ScriptSource)
+        .is_empty());
+    assert_eq!(builder.finish(), "img-src data:");
+}
+
+#[test]
+fn test_from_header_str_with_annotations() {
+    let annotations = vec![CspAnnotation {
+        directive: CspDirectiveType::ScriptSource,
+        value: None,
+        note: "only our own scripts".to_string(),
+    }];
+    let builder = CspHeaderBuilder::from_header_str_with_annotations(
+        "script-src 'self'; img-src https:",
+        annotations.clone(),
+    )
+    .expect("should parse");
+    assert_eq!(builder.annotations, annotations);
+    assert_eq!(builder.finish(), "img-src https:; script-src 'self'");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_annotations_survive_serde() {
+    let builder = CspHeaderBuilder::from_header_str("default-src 'self'; img-src https: data:")
+        .expect("should parse")
+        .annotate(CspDirectiveType::DefaultSrc, None, "catch-all")
+        .annotate(
+            CspDirectiveType::ImgSrc,
+            Some(CspValue::SchemeData),
+            "inline SVG icons",
+        );
+
+    let json = serde_json::to_string(&builder).expect("should serialize");
+    let restored: CspHeaderBuilder = serde_json::from_str(&json).expect("should deserialize");
+
+    assert_eq!(restored.annotations, builder.annotations);
+    assert_eq!(
+        restored.finish(),
+        "default-src 'self'; img-src https: data:"
+    );
+}
+
+#[test]
+fn test_to_documented_string() {
+    let builder = CspHeaderBuilder::from_header_str("default-src 'self'; img-src https: data:")
+        .expect("should parse")
+        .annotate(CspDirectiveType::DefaultSrc, None, "catch-all")
+        .annotate(
+            CspDirectiveType::ImgSrc,
+            Some(CspValue::SchemeData),
+            "inline SVG icons",
+        )
+        .annotate(CspDirectiveType::ScriptSource, None, "not in the policy");
+    assert_eq!(
+        builder.to_documented_string(),
+        "# catch-all\ndefault-src 'self';\n# data: inline SVG icons\nimg-src https: data:;\n"
+    );
+}
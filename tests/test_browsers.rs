@@ -0,0 +1,36 @@
+use axum_csp::{Browser, BrowserVersion, CspDirectiveType, CspHeaderBuilder, CspValue};
+
+#[test]
+fn test_as_enforced_by() {
+    let builder = CspHeaderBuilder::from_header_str(
+        "default-src 'self'; script-src 'nonce-abc' 'strict-dynamic' 'unsafe-inline' https:; \
+         script-src-elem 'self'; report-to csp",
+    )
+    .expect("should parse");
+
+    let old_chrome = builder.as_enforced_by(BrowserVersion::new(Browser::Chrome, 51));
+    assert_eq!(
+        old_chrome.finish_string(),
+        "default-src 'self'; script-src 'unsafe-inline' https: 'nonce-abc'"
+    );
+    assert!(!old_chrome.directive_map[&CspDirectiveType::ScriptSource]
+        .contains(&CspValue::StrictDynamic));
+
+    let new_chrome = builder.as_enforced_by(BrowserVersion::new(Browser::Chrome, 120));
+    assert_eq!(new_chrome, builder);
+
+    // Firefox doesn't do report-to
+    let firefox = builder.as_enforced_by(BrowserVersion::new(Browser::Firefox, 120));
+    assert!(!firefox
+        .directive_map
+        .contains_key(&CspDirectiveType::ReportTo));
+
+    assert_eq!(
+        CspValue::StrictDynamic.supported_since(Browser::Chrome),
+        Some(52)
+    );
+    assert_eq!(
+        CspDirectiveType::NavigateTo.supported_since(Browser::Chrome),
+        None
+    );
+}
@@ -15,3 +15,256 @@ pub fn test_csp_set_ordering() {
         "default-src 'self'; script-src 'self' 'unsafe-inline'".to_string()
     );
 }
+
+#[test]
+pub fn test_rename_report_group() {
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ReportTo,
+            vec![CspValue::Host {
+                value: "csp-endpoint".to_string(),
+            }],
+        )
+        .rename_report_group("csp-endpoint", "csp-violations")
+        // renaming a group that isn't there is a no-op
+        .rename_report_group("nope", "still-nope");
+    assert_eq!(
+        builder.finish(),
+        "default-src 'self'; report-to csp-violations".to_string()
+    );
+}
+
+#[test]
+pub fn test_finish_string_is_deterministic() {
+    let host = CspValue::Host {
+        value: "https://example.com".to_string(),
+    };
+    let first = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![CspValue::UnsafeInline, host.clone(), CspValue::SelfSite],
+        )
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SchemeData])
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SchemeHttps]);
+
+    let mut second = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ImgSrc,
+            vec![CspValue::SchemeHttps, CspValue::SchemeData],
+        )
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::ScriptSource, vec![host])
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::UnsafeInline])
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]);
+    // duplicates pushed straight into the map don't change the output either
+    second
+        .directive_map
+        .get_mut(&CspDirectiveType::DefaultSrc)
+        .expect("default-src should be set")
+        .push(CspValue::SelfSite);
+
+    let expected =
+        "default-src 'self'; img-src https: data:; script-src 'self' 'unsafe-inline' https://example.com";
+    assert_eq!(first.finish_string(), expected);
+    assert_eq!(second.finish_string(), expected);
+}
+
+#[test]
+pub fn test_policy_hash() {
+    let first = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SchemeData]);
+    let second = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SchemeData])
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]);
+    let changed = first
+        .clone()
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SchemeHttps]);
+
+    assert_eq!(first.policy_hash().len(), 16);
+    assert_eq!(first.policy_hash(), second.policy_hash());
+    assert_ne!(first.policy_hash(), changed.policy_hash());
+}
+
+#[test]
+pub fn test_add_if_absent() {
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::None])
+        .add(CspDirectiveType::ImgSrc, vec![])
+        .add_if_absent(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add_if_absent(CspDirectiveType::ImgSrc, vec![CspValue::SelfSite])
+        .add_if_absent(CspDirectiveType::FontSrc, vec![CspValue::SelfSite]);
+    assert_eq!(
+        builder.finish(),
+        "default-src 'none'; font-src 'self'; img-src 'self'"
+    );
+}
+
+#[test]
+pub fn test_finish_minified() {
+    let builder = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![CspValue::SelfSite, CspValue::SchemeHttps],
+        )
+        .add(
+            CspDirectiveType::ScriptSourceElem,
+            vec![CspValue::SchemeHttps, CspValue::SelfSite],
+        )
+        .add(CspDirectiveType::ScriptSourceAttr, vec![CspValue::None]);
+    assert_eq!(
+        builder.finish_minified(),
+        "script-src 'self' https:; script-src-attr 'none'"
+    );
+}
+
+#[test]
+pub fn test_minified_keeps_child_src_for_workers() {
+    // dropping child-src would make worker-src fall back to script-src instead
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::ChildSrc, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SchemeHttps])
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SelfSite]);
+    assert_eq!(
+        builder.minified().finish_string(),
+        "child-src 'self'; default-src 'self'; script-src https:"
+    );
+}
+
+#[test]
+pub fn test_finish_scanner_format() {
+    let builder = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![
+                CspValue::Host {
+                    value: "https://cdn.example.com".to_string(),
+                },
+                CspValue::SelfSite,
+            ],
+        )
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::None])
+        .add(CspDirectiveType::UpgradeInsecureRequests, vec![]);
+    assert_eq!(
+        builder.finish_scanner_format().as_bytes(),
+        b"default-src 'none'; script-src 'self' https://cdn.example.com; upgrade-insecure-requests;"
+    );
+    assert_eq!(CspHeaderBuilder::new().finish_scanner_format(), "");
+}
+
+#[test]
+pub fn test_canonical_scheme_ordering() {
+    let blob = CspValue::SchemeOther {
+        value: "blob:".to_string(),
+    };
+    let first = CspHeaderBuilder::new().add(
+        CspDirectiveType::ImgSrc,
+        vec![
+            CspValue::SchemeData,
+            blob.clone(),
+            CspValue::SchemeHttp,
+            CspValue::SchemeHttps,
+        ],
+    );
+    let second = CspHeaderBuilder::new().add(
+        CspDirectiveType::ImgSrc,
+        vec![
+            CspValue::SchemeHttps,
+            blob,
+            CspValue::SchemeData,
+            CspValue::SchemeHttp,
+        ],
+    );
+    assert_eq!(first.finish_string(), "img-src https: http: data: blob:");
+    assert_eq!(second.finish_string(), first.finish_string());
+}
+
+#[test]
+pub fn test_require_default_src() {
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SchemeData])
+        .require_default_src(vec![CspValue::SelfSite]);
+    assert_eq!(builder.finish_string(), "default-src 'self'; img-src data:");
+
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::None])
+        .require_default_src(vec![CspValue::SelfSite]);
+    assert_eq!(builder.finish_string(), "default-src 'none'");
+}
+
+#[test]
+#[allow(deprecated)]
+pub fn test_csp_set_builder_shim_matches_header_builder() {
+    use axum_csp::{CspDirective, CspSetBuilder};
+
+    let legacy = CspSetBuilder::new()
+        .add(CspDirective::default_self())
+        .add(CspDirective::from(
+            CspDirectiveType::ImgSrc,
+            vec![CspValue::SelfSite, CspValue::SchemeData],
+        ));
+    let modern = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ImgSrc,
+            vec![CspValue::SelfSite, CspValue::SchemeData],
+        );
+    let converted: CspHeaderBuilder = legacy.clone().into();
+    assert_eq!(converted.finish_string(), modern.finish_string());
+    assert_eq!(legacy.finish(), modern.finish());
+}
+
+#[test]
+pub fn test_unknown_directives_compare_normalized() {
+    let first = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add_unknown(
+            "future-src",
+            "  https://b.example.com   https://a.example.com ",
+        );
+    let second = CspHeaderBuilder::new()
+        .add_unknown("Future-Src", "https://a.example.com\thttps://b.example.com")
+        .add(
+            CspDirectiveType::DefaultSrc,
+            vec![CspValue::SelfSite, CspValue::SelfSite],
+        );
+    assert_eq!(first, second);
+    assert_eq!(first.policy_hash(), second.policy_hash());
+    // they're only normalized for comparing, the header keeps the order they were written in
+    assert_eq!(
+        first.finish_string(),
+        "default-src 'self'; future-src https://b.example.com https://a.example.com"
+    );
+    assert_eq!(
+        second.finish_string(),
+        "default-src 'self'; future-src https://a.example.com https://b.example.com"
+    );
+
+    assert_ne!(first, second.add_unknown("other-src", "'self'"));
+}
+
+#[test]
+pub fn test_finish_with_priority() {
+    let builder = CspHeaderBuilder::from_header_str(
+        "img-src 'self'; style-src 'self'; default-src 'none'; base-uri 'none'; script-src 'self'",
+    )
+    .expect("should parse");
+    let priority = [
+        CspDirectiveType::DefaultSrc,
+        CspDirectiveType::ScriptSource,
+        CspDirectiveType::StyleSource,
+        // not in the policy, so it's skipped
+        CspDirectiveType::ObjectSrc,
+    ];
+    assert_eq!(
+        builder.clone().finish_with_priority(&priority),
+        "default-src 'none'; script-src 'self'; style-src 'self'; base-uri 'none'; img-src 'self'"
+    );
+    assert_eq!(
+        builder.finish_string_with_priority(&[]),
+        builder.finish_string()
+    );
+}
@@ -0,0 +1,157 @@
+use axum_csp::{CspDiff, CspDirectiveType, CspHeaderBuilder, CspPatchOp, CspValue};
+
+#[test]
+fn test_diff() {
+    let before = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ImgSrc,
+            vec![CspValue::SelfSite, CspValue::SchemeData],
+        );
+    let after = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite]);
+    assert_eq!(
+        before.diff(&after),
+        CspDiff {
+            added_directives: vec![CspDirectiveType::ScriptSource],
+            removed_directives: vec![CspDirectiveType::ImgSrc],
+            added_values: vec![(CspDirectiveType::ScriptSource, CspValue::SelfSite)],
+            removed_values: vec![
+                (CspDirectiveType::ImgSrc, CspValue::SelfSite),
+                (CspDirectiveType::ImgSrc, CspValue::SchemeData),
+            ],
+            ..Default::default()
+        }
+    );
+    assert!(before.diff(&before).is_empty());
+}
+
+#[test]
+fn test_preview_add() {
+    let builder = CspHeaderBuilder::new().add(CspDirectiveType::ImgSrc, vec![CspValue::SelfSite]);
+    let values = vec![CspValue::SelfSite, CspValue::SchemeHttps];
+
+    let preview = builder.preview_add(CspDirectiveType::ImgSrc, values.clone());
+    assert_eq!(
+        preview.added_values,
+        vec![(CspDirectiveType::ImgSrc, CspValue::SchemeHttps)]
+    );
+    // the builder isn't changed, and the preview matches what actually happens
+    assert_eq!(builder.finish_string(), "img-src 'self'");
+    let added = builder.clone().add(CspDirectiveType::ImgSrc, values);
+    assert_eq!(builder.diff(&added), preview);
+}
+
+#[test]
+fn test_patch_from() {
+    let old = CspHeaderBuilder::from_header_str(
+        "default-src 'self'; img-src 'self' data:; object-src 'none'",
+    )
+    .expect("should parse")
+    .add_unknown("future-src", "'self'");
+    let new = CspHeaderBuilder::from_header_str(
+        "default-src 'self'; img-src 'self' https:; script-src 'self' 'nonce-abc'",
+    )
+    .expect("should parse");
+
+    let patch = CspHeaderBuilder::patch_from(&old, &new);
+    assert_eq!(
+        patch.operations,
+        vec![
+            CspPatchOp::RemoveValue {
+                directive: CspDirectiveType::ImgSrc,
+                value: CspValue::SchemeData,
+            },
+            CspPatchOp::AddValue {
+                directive: CspDirectiveType::ImgSrc,
+                value: CspValue::SchemeHttps,
+            },
+            CspPatchOp::RemoveDirective {
+                directive: CspDirectiveType::ObjectSrc,
+            },
+            CspPatchOp::AddDirective {
+                directive: CspDirectiveType::ScriptSource,
+                values: vec![
+                    CspValue::SelfSite,
+                    CspValue::Nonce {
+                        value: "abc".to_string(),
+                    },
+                ],
+            },
+            CspPatchOp::RemoveUnknown {
+                name: "future-src".to_string(),
+            },
+        ]
+    );
+    assert_eq!(patch.apply(old.clone()), new);
+    assert!(CspHeaderBuilder::patch_from(&new, &new).is_empty());
+}
+
+#[test]
+fn test_assert_eq_policy() {
+    let gateway = CspHeaderBuilder::from_header_str("default-src 'self'; img-src 'self' data:")
+        .expect("should parse");
+    let same = CspHeaderBuilder::from_header_str("img-src data: 'self' data:; default-src 'self'")
+        .expect("should parse");
+    assert_eq!(gateway.assert_eq_policy(&same), Ok(()));
+
+    let backend = CspHeaderBuilder::from_header_str("default-src 'self'; img-src 'self' https:")
+        .expect("should parse");
+    assert_eq!(
+        gateway.assert_eq_policy(&backend),
+        Err(CspDiff {
+            added_values: vec![(CspDirectiveType::ImgSrc, CspValue::SchemeHttps)],
+            removed_values: vec![(CspDirectiveType::ImgSrc, CspValue::SchemeData)],
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_assert_eq_policy_unknown_directive() {
+    let gateway = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add_unknown("x-foo", "a  b");
+    let same = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add_unknown("x-foo", "b a");
+    assert_eq!(gateway.assert_eq_policy(&same), Ok(()));
+
+    let backend = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add_unknown("x-foo", "c")
+        .add_unknown("x-bar", "d");
+    assert_eq!(
+        gateway.assert_eq_policy(&backend),
+        Err(CspDiff {
+            added_unknown_directives: vec![
+                ("x-bar".to_string(), "d".to_string()),
+                ("x-foo".to_string(), "c".to_string()),
+            ],
+            removed_unknown_directives: vec![("x-foo".to_string(), "a  b".to_string())],
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_assert_eq_policy_conditional_directive() {
+    let widget = CspValue::Host {
+        value: "https://widget.example.com".to_string(),
+    };
+    let gateway = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite])
+        .add_conditional(CspDirectiveType::ScriptSource, vec![widget.clone()], || {
+            true
+        });
+    let backend =
+        CspHeaderBuilder::new().add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite]);
+    assert_eq!(
+        gateway.assert_eq_policy(&backend),
+        Err(CspDiff {
+            removed_values: vec![(CspDirectiveType::ScriptSource, widget)],
+            ..Default::default()
+        })
+    );
+}
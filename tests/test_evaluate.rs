@@ -0,0 +1,106 @@
+use axum_csp::{CspDirectiveType, CspHeaderBuilder};
+
+#[test]
+fn test_check_loads() {
+    let builder = CspHeaderBuilder::from_header_str(
+        "default-src 'self'; img-src 'self' data: https://*.images.example.com; \
+         script-src https://cdn.example.com/js/ https://static.example.com:8443/app.js; \
+         connect-src wss://live.example.com *",
+    )
+    .expect("failed to parse");
+
+    let loads = [
+        (
+            CspDirectiveType::ScriptSource,
+            "https://cdn.example.com/js/app.js",
+            true,
+        ),
+        (
+            CspDirectiveType::ScriptSource,
+            "https://cdn.example.com/other.js",
+            false,
+        ),
+        (
+            CspDirectiveType::ScriptSource,
+            "https://static.example.com:8443/app.js",
+            true,
+        ),
+        (
+            CspDirectiveType::ScriptSource,
+            "https://static.example.com/app.js",
+            false,
+        ),
+        (
+            CspDirectiveType::ScriptSource,
+            "https://example.com/main.js",
+            false,
+        ),
+        (
+            CspDirectiveType::ImgSrc,
+            "https://example.com/logo.png",
+            true,
+        ),
+        (CspDirectiveType::ImgSrc, "data:image/png;base64,AAAA", true),
+        (
+            CspDirectiveType::ImgSrc,
+            "https://a.images.example.com/x.png",
+            true,
+        ),
+        (
+            CspDirectiveType::ImgSrc,
+            "https://images.example.com/x.png",
+            false,
+        ),
+        (
+            CspDirectiveType::ImgSrc,
+            "http://example.com/logo.png",
+            false,
+        ),
+        (
+            CspDirectiveType::ConnectSrc,
+            "wss://live.example.com/feed",
+            true,
+        ),
+        (
+            CspDirectiveType::ConnectSrc,
+            "https://anything.example.net",
+            true,
+        ),
+        (
+            CspDirectiveType::ConnectSrc,
+            "blob:https://example.com/1234",
+            false,
+        ),
+        // falls back to default-src 'self'
+        (
+            CspDirectiveType::FontSrc,
+            "https://example.com/font.woff2",
+            true,
+        ),
+        (
+            CspDirectiveType::FontSrc,
+            "https://fonts.example.net/font.woff2",
+            false,
+        ),
+    ];
+    let blocked = builder.check_loads(
+        &loads.map(|(directive, url, _)| (directive, url)),
+        "https://example.com",
+    );
+    let blocked_urls: Vec<&str> = blocked.iter().map(|b| b.url.as_str()).collect();
+    for (_, url, allowed) in loads {
+        assert_eq!(!blocked_urls.contains(&url), allowed, "{url}");
+    }
+    assert_eq!(
+        blocked.last().map(|b| b.reason.as_str()),
+        Some("not allowed by default-src, which font-src falls back to")
+    );
+
+    // without a matching directive there's nothing to block
+    assert!(CspHeaderBuilder::new()
+        .check_loads(
+            &[(CspDirectiveType::ImgSrc, "https://example.net/x.png")],
+            "https://example.com"
+        )
+        .is_empty());
+}
@@ -0,0 +1,29 @@
+use axum_csp::CspDirectiveType;
+
+#[test]
+fn test_script_src_value_hints() {
+    let hints = CspDirectiveType::ScriptSource.value_hints();
+    let hint = |token: &str| {
+        hints
+            .iter()
+            .find(|h| h.token == token)
+            .unwrap_or_else(|| panic!("no hint for {token}"))
+    };
+
+    let strict_dynamic = hint("'strict-dynamic'");
+    assert!(!strict_dynamic.dangerous);
+    assert!(!strict_dynamic.experimental);
+    assert!(!strict_dynamic.description.is_empty());
+    assert!(hint("'unsafe-inline'").dangerous);
+    assert!(hint("'unsafe-eval'").dangerous);
+    assert_eq!(hint("'self'").description, "same-origin resources");
+    hint("'nonce-<base64>'");
+
+    // img-src has no use for script keywords or nonces
+    let img = CspDirectiveType::ImgSrc.value_hints();
+    assert!(!img.iter().any(|h| h.token == "'strict-dynamic'"));
+    assert!(!img.iter().any(|h| h.token == "'nonce-<base64>'"));
+    assert!(CspDirectiveType::UpgradeInsecureRequests
+        .value_hints()
+        .is_empty());
+}
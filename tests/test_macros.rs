@@ -0,0 +1,36 @@
+use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue};
+
+#[test]
+fn test_csp_macro() {
+    let builder = axum_csp::csp! {
+        "default-src" => ["'self'"],
+        "Script-Src" => ["'self'", "'nonce-abc123'", "'strict-dynamic'"],
+        "require-trusted-types-for" => ["'script'"],
+        "upgrade-insecure-requests" => [],
+    };
+    let expected = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![
+                CspValue::SelfSite,
+                CspValue::Nonce {
+                    value: "abc123".to_string(),
+                },
+                CspValue::StrictDynamic,
+            ],
+        )
+        .add(
+            CspDirectiveType::RequireTrustedTypesFor,
+            vec![CspValue::ScriptSink],
+        )
+        .add(CspDirectiveType::UpgradeInsecureRequests, vec![]);
+    assert_eq!(builder, expected);
+    assert!(builder.validate().is_empty());
+}
+
+#[test]
+fn test_csp_macro_compile_errors() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
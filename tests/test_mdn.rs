@@ -0,0 +1,103 @@
+//! Every directive and source keyword documented on MDN should be representable, parse and
+//! round-trip: <https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Security-Policy>
+
+use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue};
+use std::str::FromStr;
+
+/// Each directive on MDN, with an example value
+const MDN_DIRECTIVES: &[(&str, &str)] = &[
+    ("base-uri", "'self'"),
+    ("block-all-mixed-content", ""),
+    ("child-src", "https://example.com"),
+    ("connect-src", "wss://example.com"),
+    ("default-src", "'self'"),
+    ("fenced-frame-src", "https:"),
+    ("font-src", "https://fonts.example.com"),
+    ("form-action", "'self'"),
+    ("frame-ancestors", "'none'"),
+    ("frame-src", "https://example.com"),
+    ("img-src", "data: blob:"),
+    ("manifest-src", "'self'"),
+    ("media-src", "mediastream:"),
+    ("navigate-to", "'unsafe-allow-redirects'"),
+    ("object-src", "'none'"),
+    ("prefetch-src", "'self'"),
+    ("report-to", "csp-endpoint"),
+    ("report-uri", "https://example.com/csp"),
+    ("require-sri-for", "script style"),
+    ("require-trusted-types-for", "'script'"),
+    ("sandbox", "allow-forms allow-scripts"),
+    (
+        "script-src",
+        "'self' 'unsafe-eval' 'wasm-unsafe-eval' 'inline-speculation-rules'",
+    ),
+    ("script-src-attr", "'unsafe-hashes' 'sha256-abc='"),
+    (
+        "script-src-elem",
+        "'strict-dynamic' 'nonce-abc' 'trusted-types-eval'",
+    ),
+    ("style-src", "'unsafe-inline' 'report-sample'"),
+    ("style-src-attr", "'sha384-abc='"),
+    ("style-src-elem", "'sha512-abc='"),
+    ("trusted-types", "default 'allow-duplicates'"),
+    ("upgrade-insecure-requests", ""),
+    ("worker-src", "blob:"),
+];
+
+/// Each source keyword on MDN, and the value it parses to
+const MDN_KEYWORDS: &[(&str, CspValue)] = &[
+    ("'none'", CspValue::None),
+    ("'self'", CspValue::SelfSite),
+    ("'strict-dynamic'", CspValue::StrictDynamic),
+    ("'report-sample'", CspValue::ReportSample),
+    (
+        "'inline-speculation-rules'",
+        CspValue::InlineSpeculationRules,
+    ),
+    ("'unsafe-inline'", CspValue::UnsafeInline),
+    ("'unsafe-eval'", CspValue::UnsafeEval),
+    ("'wasm-unsafe-eval'", CspValue::WasmUnsafeEval),
+    ("'unsafe-hashes'", CspValue::UnsafeHashes),
+    ("'unsafe-allow-redirects'", CspValue::UnsafeAllowRedirects),
+    ("'trusted-types-eval'", CspValue::TrustedTypesEval),
+    ("'allow-duplicates'", CspValue::AllowDuplicates),
+    ("https:", CspValue::SchemeHttps),
+    ("http:", CspValue::SchemeHttp),
+    ("data:", CspValue::SchemeData),
+];
+
+#[test]
+fn test_mdn_directives_round_trip() {
+    assert_eq!(CspDirectiveType::ALL.len(), MDN_DIRECTIVES.len());
+    for ((name, _), directive) in MDN_DIRECTIVES.iter().zip(CspDirectiveType::ALL) {
+        assert_eq!(directive.as_ref(), *name);
+        assert_eq!(
+            CspDirectiveType::from_str(name).expect("should parse"),
+            *directive
+        );
+    }
+
+    let policy = MDN_DIRECTIVES
+        .iter()
+        .map(|(name, value)| format!("{name} {value}").trim().to_string())
+        .collect::<Vec<String>>()
+        .join("; ");
+    let builder = CspHeaderBuilder::from_header_str_strict(&policy).expect("should parse");
+    assert_eq!(builder.directive_map.len(), MDN_DIRECTIVES.len());
+    let reparsed =
+        CspHeaderBuilder::from_header_str_strict(&builder.finish_string()).expect("should parse");
+    assert_eq!(reparsed, builder);
+    for (directive, values) in &builder.directive_map {
+        let mut values = values.clone();
+        values.sort();
+        assert_eq!(reparsed.directive_map[directive], values, "{directive}");
+    }
+}
+
+#[test]
+fn test_mdn_keywords_round_trip() {
+    for (token, value) in MDN_KEYWORDS {
+        assert_eq!(&CspValue::from_str(token).expect("should parse"), value);
+        assert_eq!(String::from(value.to_owned()), *token);
+    }
+}
@@ -0,0 +1,175 @@
+use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue, Severity};
+
+#[test]
+fn test_merge() {
+    let base = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SelfSite]);
+    let extra = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SchemeData])
+        .add(CspDirectiveType::FontSrc, vec![CspValue::SchemeHttps]);
+    assert_eq!(
+        base.merge(extra).finish_string(),
+        "default-src 'self'; font-src https:; img-src 'self' data:"
+    );
+}
+
+#[test]
+fn test_merge_none_exclusivity() {
+    let host = CspValue::Host {
+        value: "https://x".to_string(),
+    };
+    let (merged, warnings) = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ObjectSrc, vec![CspValue::None])
+        .merge_with_warnings(
+            CspHeaderBuilder::new().add(CspDirectiveType::ObjectSrc, vec![host.clone()]),
+        );
+    assert_eq!(
+        merged.directive_map.get(&CspDirectiveType::ObjectSrc),
+        Some(&vec![host])
+    );
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert_eq!(warnings[0].directive, Some(CspDirectiveType::ObjectSrc));
+
+    // 'none' merged with 'none' stays put, quietly
+    let (merged, warnings) = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ObjectSrc, vec![CspValue::None])
+        .merge_with_warnings(
+            CspHeaderBuilder::new().add(CspDirectiveType::ObjectSrc, vec![CspValue::None]),
+        );
+    assert_eq!(merged.finish_string(), "object-src 'none'");
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_policy_accumulator_threads() {
+    use axum_csp::CspPolicyAccumulator;
+    use std::sync::Arc;
+
+    let accumulator = Arc::new(CspPolicyAccumulator::from_builder(
+        CspHeaderBuilder::new().add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]),
+    ));
+    let threads: Vec<_> = (0..8)
+        .map(|i| {
+            let accumulator = accumulator.clone();
+            std::thread::spawn(move || {
+                accumulator.add(
+                    CspDirectiveType::ImgSrc,
+                    vec![CspValue::Host {
+                        value: format!("https://img{i}.example.com"),
+                    }],
+                );
+                accumulator.merge(
+                    CspHeaderBuilder::new()
+                        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite]),
+                );
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().expect("thread panicked");
+    }
+
+    let builder = accumulator.finalize();
+    assert_eq!(
+        builder
+            .directive_map
+            .get(&CspDirectiveType::ImgSrc)
+            .map(Vec::len),
+        Some(8)
+    );
+    assert_eq!(
+        builder.directive_map.get(&CspDirectiveType::ScriptSource),
+        Some(&vec![CspValue::SelfSite])
+    );
+    assert_eq!(
+        builder.directive_map.get(&CspDirectiveType::DefaultSrc),
+        Some(&vec![CspValue::SelfSite])
+    );
+}
+
+#[test]
+fn test_merge_guarded() {
+    use axum_csp::{AllowedValues, MergePolicy, Rejection};
+
+    let base = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SelfSite]);
+    let allowed = MergePolicy::new().allow(CspDirectiveType::ImgSrc, AllowedValues::HostsOnly);
+    let cdn = CspValue::Host {
+        value: "https://images.example.com".to_string(),
+    };
+
+    let images = CspHeaderBuilder::new().add(CspDirectiveType::ImgSrc, vec![cdn.clone()]);
+    let merged = base
+        .clone()
+        .merge_guarded(images, &allowed)
+        .expect("adding image hosts is allowed");
+    assert_eq!(
+        merged.finish_string(),
+        "default-src 'self'; img-src 'self' https://images.example.com; script-src 'self'"
+    );
+
+    let scripts = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![cdn, CspValue::SelfSite],
+        )
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::UnsafeInline]);
+    assert_eq!(
+        base.merge_guarded(scripts, &allowed),
+        Err(vec![
+            Rejection {
+                directive: Some(CspDirectiveType::ImgSrc),
+                value: Some(CspValue::UnsafeInline),
+                reason: "'unsafe-inline' isn't an allowed value for img-src".to_string(),
+            },
+            Rejection {
+                directive: Some(CspDirectiveType::ScriptSource),
+                value: Some(CspValue::Host {
+                    value: "https://images.example.com".to_string(),
+                }),
+                reason: "script-src can't be changed".to_string(),
+            },
+        ])
+    );
+}
+
+#[test]
+fn test_merge_keeps_conditional_directives() {
+    use axum_csp::{AllowedValues, MergePolicy};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let enabled = Arc::new(AtomicBool::new(false));
+    let flag = enabled.clone();
+    let widget = CspHeaderBuilder::new().add_conditional(
+        CspDirectiveType::FrameSrc,
+        vec![CspValue::Host {
+            value: "https://widget.example.com".to_string(),
+        }],
+        move || flag.load(Ordering::SeqCst),
+    );
+    let base = CspHeaderBuilder::new().add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]);
+
+    let merged = base.clone().merge(widget.clone());
+    assert_eq!(merged.conditional_directives.len(), 1);
+    assert_eq!(merged.finish_string(), "default-src 'self'");
+    enabled.store(true, Ordering::SeqCst);
+    assert_eq!(
+        merged.finish_string(),
+        "default-src 'self'; frame-src https://widget.example.com"
+    );
+
+    // guarded merges check conditional values too
+    let rejections = base
+        .clone()
+        .merge_guarded(widget.clone(), &MergePolicy::new())
+        .expect_err("frame-src isn't allowed");
+    assert_eq!(rejections.len(), 1);
+    assert_eq!(rejections[0].directive, Some(CspDirectiveType::FrameSrc));
+    let allowed = MergePolicy::new().allow(CspDirectiveType::FrameSrc, AllowedValues::HostsOnly);
+    assert!(base.merge_guarded(widget, &allowed).is_ok());
+}
@@ -0,0 +1,98 @@
+use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue};
+
+#[test]
+fn test_to_meta_tag() {
+    let meta = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .to_meta_tag();
+    assert_eq!(
+        meta,
+        r#"<meta http-equiv="Content-Security-Policy" content="default-src 'self'">"#
+    );
+}
+
+#[test]
+fn test_to_meta_tag_escapes_nasty_host() {
+    let meta = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ImgSrc,
+            vec![CspValue::Host {
+                value: r#"example.com"><script>alert(1)</script>&"#.to_string(),
+            }],
+        )
+        .to_meta_tag();
+    assert_eq!(
+        meta,
+        r#"<meta http-equiv="Content-Security-Policy" content="img-src example.com&quot;&gt;&lt;script&gt;alert(1)&lt;/script&gt;&amp;">"#
+    );
+    // only the tag's own delimiters survive
+    assert_eq!(meta.matches('"').count(), 4);
+    assert_eq!(meta.matches('>').count(), 1);
+}
+
+#[test]
+fn test_split_header_and_meta() {
+    let (header, meta) = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::FrameAncestors, vec![CspValue::None])
+        .add(CspDirectiveType::Sandbox, vec![])
+        .split_header_and_meta();
+    assert_eq!(header.finish_string(), "frame-ancestors 'none'; sandbox");
+    assert_eq!(meta.finish_string(), "default-src 'self'");
+    assert!(!meta
+        .directive_map
+        .contains_key(&CspDirectiveType::FrameAncestors));
+}
+
+#[test]
+fn test_from_html_meta() {
+    let html = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width">
+    <META HTTP-EQUIV='Content-Security-Policy'
+          Content="default-src 'self'; img-src https: data:">
+  </head>
+  <body></body>
+</html>"#;
+    let builder = CspHeaderBuilder::from_html_meta(html)
+        .expect("should parse")
+        .expect("should find the tag");
+    assert_eq!(
+        builder.finish_string(),
+        "default-src 'self'; img-src https: data:"
+    );
+
+    // what to_meta_tag renders can be read back
+    let tag = builder.to_meta_tag();
+    assert_eq!(
+        CspHeaderBuilder::from_html_meta(&tag).expect("should parse"),
+        Some(builder)
+    );
+
+    assert_eq!(
+        CspHeaderBuilder::from_html_meta("<html><meta charset=utf-8></html>"),
+        Ok(None)
+    );
+    assert!(CspHeaderBuilder::from_html_meta(
+        r#"<meta http-equiv=content-security-policy content="foo-src 'self'">"#
+    )
+    .is_err());
+}
+
+#[test]
+fn test_split_header_and_meta_keeps_conditional_directives() {
+    let builder = CspHeaderBuilder::new()
+        .add_conditional(
+            CspDirectiveType::FrameAncestors,
+            vec![CspValue::SelfSite],
+            || true,
+        )
+        .add_conditional(CspDirectiveType::ImgSrc, vec![CspValue::SchemeData], || {
+            true
+        });
+    let (header, meta) = builder.split_header_and_meta();
+    assert_eq!(header.finish_string(), "frame-ancestors 'self'");
+    assert_eq!(meta.finish_string(), "img-src data:");
+}
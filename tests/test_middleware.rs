@@ -0,0 +1,354 @@
+use axum::body::Body;
+use axum::http::header::CONTENT_SECURITY_POLICY;
+use axum::http::{Method, Request};
+use axum::middleware::from_fn_with_state;
+use axum::routing::get;
+use axum::{Extension, Router};
+use axum_csp::middleware::{
+    csp_matcher_middleware, csp_nonce_middleware, CspMatcherConfig, CspNonceConfig, RouterCspExt,
+};
+use axum_csp::{
+    CspDirective, CspDirectiveType, CspHeaderBuilder, CspUrlMatcher, CspValue, Nonce, NonceSource,
+    ReportingConfig, REPORTING_ENDPOINTS,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tower::ServiceExt;
+
+/// Hands out `test0`, `test1`, ... so the header is predictable
+#[derive(Default)]
+struct SequenceNonceSource(AtomicUsize);
+
+impl NonceSource for SequenceNonceSource {
+    fn generate(&self) -> Nonce {
+        Nonce::new(format!("test{}", self.0.fetch_add(1, Ordering::SeqCst)))
+    }
+}
+
+fn nonce_router() -> Router {
+    let config = CspNonceConfig::new(
+        CspHeaderBuilder::new().add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]),
+        vec![CspDirectiveType::ScriptSource],
+    )
+    .with_nonce_source(SequenceNonceSource::default());
+
+    Router::new()
+        .route(
+            "/",
+            get(|Extension(nonce): Extension<Nonce>| async move { nonce.to_string() }),
+        )
+        .layer(from_fn_with_state(config, csp_nonce_middleware))
+}
+
+#[tokio::test]
+async fn test_nonce_middleware_uses_nonce_source() {
+    let router = nonce_router();
+    for expected in ["test0", "test1"] {
+        let response = router
+            .clone()
+            .oneshot(Request::get("/").body(Body::empty()).expect("request"))
+            .await
+            .expect("response");
+        assert_eq!(
+            response
+                .headers()
+                .get(CONTENT_SECURITY_POLICY)
+                .expect("header"),
+            &format!("default-src 'self'; script-src 'nonce-{expected}'")
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        assert_eq!(body, expected);
+    }
+}
+
+#[test]
+fn test_random_nonce_source() {
+    let source = axum_csp::RandomNonceSource;
+    let first = source.generate();
+    assert_eq!(first.value().len(), 24);
+    assert_ne!(first, source.generate());
+}
+
+#[tokio::test]
+async fn test_nonce_middleware_reporting() {
+    let config = CspNonceConfig::new(
+        CspHeaderBuilder::new().add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]),
+        vec![CspDirectiveType::ScriptSource],
+    )
+    .with_nonce_source(SequenceNonceSource::default())
+    .with_reporting(ReportingConfig::new("csp", "https://example.com/csp"))
+    .expect("valid reporting config");
+    let router = Router::new()
+        .route("/", get(|| async { "hello" }))
+        .layer(from_fn_with_state(config, csp_nonce_middleware));
+
+    let response = router
+        .oneshot(Request::get("/").body(Body::empty()).expect("request"))
+        .await
+        .expect("response");
+    assert_eq!(
+        response
+            .headers()
+            .get(CONTENT_SECURITY_POLICY)
+            .expect("header"),
+        "default-src 'self'; report-to csp; script-src 'nonce-test0'"
+    );
+    assert_eq!(
+        response.headers().get(REPORTING_ENDPOINTS).expect("header"),
+        r#"csp="https://example.com/csp""#
+    );
+}
+
+#[tokio::test]
+async fn test_router_with_csp() {
+    let policy =
+        CspHeaderBuilder::new().add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]);
+    let static_router = Router::new()
+        .route("/", get(|| async { "hello" }))
+        .with_csp(policy.clone());
+    let nonce_router = Router::new()
+        .route("/", get(|| async { "hello" }))
+        .with_csp(
+            CspNonceConfig::new(policy, vec![CspDirectiveType::ScriptSource])
+                .with_nonce_source(SequenceNonceSource::default()),
+        );
+
+    for (router, expected) in [
+        (static_router, "default-src 'self'"),
+        (nonce_router, "default-src 'self'; script-src 'nonce-test0'"),
+    ] {
+        let response = router
+            .oneshot(Request::get("/").body(Body::empty()).expect("request"))
+            .await
+            .expect("response");
+        assert_eq!(
+            response
+                .headers()
+                .get(CONTENT_SECURITY_POLICY)
+                .expect("header"),
+            expected
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_matcher_middleware_by_method() {
+    let matcher = |policy: CspValue| {
+        CspUrlMatcher::new(regex::RegexSet::new(["^/x$"]).expect("regex"))
+            .with_directive(CspDirective::from(
+                CspDirectiveType::DefaultSrc,
+                vec![policy],
+            ))
+            .to_owned()
+    };
+    let config = CspMatcherConfig::with_methods(vec![
+        (Some(Method::POST), matcher(CspValue::None)),
+        (None, matcher(CspValue::SelfSite)),
+    ]);
+    let router = Router::new()
+        .route("/x", get(|| async { "get" }).post(|| async { "post" }))
+        .route("/y", get(|| async { "unmatched" }))
+        .layer(from_fn_with_state(config, csp_matcher_middleware));
+
+    for (method, path, expected) in [
+        (Method::GET, "/x", Some("default-src 'self';")),
+        (Method::POST, "/x", Some("default-src 'none';")),
+        (Method::GET, "/y", None),
+    ] {
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(path)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(
+            response
+                .headers()
+                .get(CONTENT_SECURITY_POLICY)
+                .map(|h| h.to_str().expect("ascii")),
+            expected
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_matcher_middleware_matched_path() {
+    let users = CspUrlMatcher::new(regex::RegexSet::new([r"^/users/\{id\}$"]).expect("regex"))
+        .with_directive(CspDirective::from(
+            CspDirectiveType::DefaultSrc,
+            vec![CspValue::SelfSite],
+        ))
+        .to_owned();
+    let router = |config: CspMatcherConfig| {
+        Router::new()
+            .route("/users/{id}", get(|| async { "user" }))
+            .layer(from_fn_with_state(config, csp_matcher_middleware))
+    };
+
+    for (config, expected) in [
+        (
+            CspMatcherConfig::new(vec![users.clone()]).with_matched_path(),
+            Some("default-src 'self';"),
+        ),
+        // the concrete path doesn't match the template
+        (CspMatcherConfig::new(vec![users]), None),
+    ] {
+        let response = router(config)
+            .oneshot(
+                Request::get("/users/42")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(
+            response
+                .headers()
+                .get(CONTENT_SECURITY_POLICY)
+                .map(|h| h.to_str().expect("ascii")),
+            expected
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_nonce_middleware_one_nonce_per_request() {
+    let config = CspNonceConfig::new(
+        CspHeaderBuilder::new().add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]),
+        vec![
+            CspDirectiveType::ScriptSource,
+            CspDirectiveType::StyleSource,
+        ],
+    );
+    let router = Router::new()
+        .route(
+            "/",
+            get(|Extension(nonce): Extension<Nonce>| async move { nonce.to_string() }),
+        )
+        .layer(from_fn_with_state(config, csp_nonce_middleware));
+
+    let response = router
+        .oneshot(Request::get("/").body(Body::empty()).expect("request"))
+        .await
+        .expect("response");
+    let header = CspHeaderBuilder::from_header_str(
+        response
+            .headers()
+            .get(CONTENT_SECURITY_POLICY)
+            .expect("header")
+            .to_str()
+            .expect("ascii"),
+    )
+    .expect("should parse");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("body");
+    let expected = vec![CspValue::Nonce {
+        value: String::from_utf8(body.to_vec()).expect("utf8"),
+    }];
+    assert_eq!(
+        header.directive_map.get(&CspDirectiveType::ScriptSource),
+        Some(&expected)
+    );
+    assert_eq!(
+        header.directive_map.get(&CspDirectiveType::StyleSource),
+        Some(&expected)
+    );
+}
+
+#[tokio::test]
+async fn test_streaming_response_has_csp_on_head() {
+    use futures_util::{stream, StreamExt};
+    use std::convert::Infallible;
+
+    // the first event is sent, then the stream stays open forever, like an idle SSE connection
+    let router = Router::new()
+        .route(
+            "/events",
+            get(|| async {
+                let events = stream::once(async { Ok::<_, Infallible>("data: hello\n\n") })
+                    .chain(stream::pending());
+                Body::from_stream(events)
+            }),
+        )
+        .with_csp(
+            CspHeaderBuilder::new().add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]),
+        );
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri("/events")
+                .body(Body::empty())
+                .expect("failed to build request"),
+        )
+        .await
+        .expect("request failed");
+    assert_eq!(
+        response.headers().get(CONTENT_SECURITY_POLICY),
+        Some(&"default-src 'self'".parse().expect("invalid header value"))
+    );
+
+    let mut body = response.into_body().into_data_stream();
+    let first = body
+        .next()
+        .await
+        .expect("stream ended")
+        .expect("stream failed");
+    assert_eq!(&first[..], b"data: hello\n\n");
+}
+
+#[tokio::test]
+async fn test_router_with_csp_follows_conditional_directives() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let enabled = Arc::new(AtomicBool::new(false));
+    let flag = enabled.clone();
+    let router = Router::new()
+        .route("/", get(|| async { "hello" }))
+        .with_csp(
+            CspHeaderBuilder::new()
+                .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+                .add_conditional(
+                    CspDirectiveType::FrameSrc,
+                    vec![CspValue::Host {
+                        value: "https://widget.example.com".to_string(),
+                    }],
+                    move || flag.load(Ordering::SeqCst),
+                ),
+        );
+    let request = || {
+        Request::builder()
+            .uri("/")
+            .body(Body::empty())
+            .expect("failed to build request")
+    };
+
+    let response = router
+        .clone()
+        .oneshot(request())
+        .await
+        .expect("request failed");
+    assert_eq!(
+        response.headers().get(CONTENT_SECURITY_POLICY),
+        Some(&"default-src 'self'".parse().expect("invalid header value"))
+    );
+
+    enabled.store(true, Ordering::SeqCst);
+    let response = router.oneshot(request()).await.expect("request failed");
+    assert_eq!(
+        response.headers().get(CONTENT_SECURITY_POLICY),
+        Some(
+            &"default-src 'self'; frame-src https://widget.example.com"
+                .parse()
+                .expect("invalid header value")
+        )
+    );
+}
@@ -0,0 +1,69 @@
+use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue, Nonce, NonceContext};
+
+#[test]
+fn test_finish_with_context() {
+    let builder = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![CspValue::NoncePlaceholder, CspValue::StrictDynamic],
+        )
+        .add(
+            CspDirectiveType::StyleSource,
+            vec![CspValue::SelfSite, CspValue::NoncePlaceholder],
+        );
+    let ctx = NonceContext::new(Nonce::new("r4nd0m"));
+    assert_eq!(
+        builder.clone().finish_with_context(&ctx),
+        "script-src 'strict-dynamic' 'nonce-r4nd0m'; style-src 'self' 'nonce-r4nd0m'"
+    );
+    // unresolved placeholders fail closed
+    assert_eq!(
+        builder.finish(),
+        "script-src 'strict-dynamic' 'none'; style-src 'self' 'none'"
+    );
+}
+
+#[test]
+fn test_nonce_config() {
+    use axum_csp::middleware::CspNonceConfig;
+    use axum_csp::{NonceConfig, NonceEncoding, NonceLengthError, NonceSource};
+    use base64::engine::general_purpose::{STANDARD, URL_SAFE};
+    use base64::Engine;
+
+    let config = NonceConfig::new(32).expect("32 bytes is long enough");
+    let nonce = config.generate();
+    assert_eq!(STANDARD.decode(nonce.value()).expect("base64").len(), 32);
+    assert_ne!(nonce, config.generate());
+
+    let url_safe = config.with_encoding(NonceEncoding::Base64Url).generate();
+    assert_eq!(URL_SAFE.decode(url_safe.value()).expect("base64").len(), 32);
+    assert!(!url_safe.value().contains(['+', '/']));
+
+    assert_eq!(
+        NonceConfig::new(8),
+        Err(NonceLengthError { byte_length: 8 })
+    );
+    assert_eq!(NonceConfig::default().byte_length(), 16);
+
+    // the middleware takes it like any other nonce source
+    let middleware = CspNonceConfig::new(
+        CspHeaderBuilder::new(),
+        vec![CspDirectiveType::ScriptSource],
+    )
+    .with_nonce_source(config);
+    let nonce = middleware.nonce_source.generate();
+    assert_eq!(STANDARD.decode(nonce.value()).expect("base64").len(), 32);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_nonce_config_from_config_file() {
+    use axum_csp::{NonceConfig, NonceEncoding};
+
+    let config: NonceConfig =
+        serde_json::from_str(r#"{"byte_length": 32, "encoding": "base64-url"}"#)
+            .expect("should deserialize");
+    assert_eq!(config.byte_length(), 32);
+    assert_eq!(config.encoding(), NonceEncoding::Base64Url);
+    assert!(serde_json::from_str::<NonceConfig>(r#"{"byte_length": 8}"#).is_err());
+}
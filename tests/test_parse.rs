@@ -0,0 +1,257 @@
+use axum_csp::{ConfigError, CspDirectiveType, CspHeaderBuilder, CspValue, ParseError};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[test]
+fn test_directive_type_from_str() {
+    for directive in CspDirectiveType::ALL {
+        assert_eq!(
+            CspDirectiveType::from_str(directive.as_ref()),
+            Ok(*directive)
+        );
+    }
+    assert_eq!(
+        CspDirectiveType::from_str("Script-SRC"),
+        Ok(CspDirectiveType::ScriptSource)
+    );
+}
+
+#[test]
+fn test_value_from_str() {
+    let cases = [
+        ("'self'", CspValue::SelfSite),
+        ("https:", CspValue::SchemeHttps),
+        (
+            "blob:",
+            CspValue::SchemeOther {
+                value: "blob:".to_string(),
+            },
+        ),
+        (
+            "'nonce-AbC+/='",
+            CspValue::Nonce {
+                value: "AbC+/=".to_string(),
+            },
+        ),
+        (
+            "https://example.com",
+            CspValue::Host {
+                value: "https://example.com".to_string(),
+            },
+        ),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(CspValue::from_str(input), Ok(expected.clone()));
+        // and it renders back the same way
+        assert_eq!(String::from(expected), input);
+    }
+    // keywords are case-insensitive
+    assert_eq!(CspValue::from_str("'NONE'"), Ok(CspValue::None));
+
+    assert!(CspValue::from_str("'bogus'").is_err());
+    assert!(CspValue::from_str("foo bar").is_err());
+    assert!(CspValue::from_str("").is_err());
+}
+
+#[test]
+fn test_try_from_map() {
+    let map = HashMap::from([
+        (
+            "script-src".to_string(),
+            vec!["'self'".to_string(), "https:".to_string()],
+        ),
+        ("upgrade-insecure-requests".to_string(), vec![]),
+    ]);
+    let builder = CspHeaderBuilder::try_from_map(map).expect("should parse");
+    assert_eq!(
+        builder.finish_string(),
+        "script-src 'self' https:; upgrade-insecure-requests"
+    );
+}
+
+#[test]
+fn test_try_from_map_collects_all_errors() {
+    let map = HashMap::from([
+        ("img-src".to_string(), vec!["'self'".to_string()]),
+        ("scrpit-src".to_string(), vec!["'self'".to_string()]),
+        (
+            "style-src".to_string(),
+            vec!["'unsafe-inlined'".to_string()],
+        ),
+    ]);
+    let ConfigError { errors } = CspHeaderBuilder::try_from_map(map).expect_err("should fail");
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        errors[0],
+        ParseError::UnknownDirective {
+            name: "scrpit-src".to_string()
+        }
+    );
+    assert!(matches!(
+        &errors[1],
+        ParseError::InvalidValue { value, .. } if value == "'unsafe-inlined'"
+    ));
+}
+
+#[test]
+fn test_from_header_str() {
+    let builder = CspHeaderBuilder::from_header_str(
+        " Default-Src 'self';; img-src https: data: ;default-src 'none'; upgrade-insecure-requests",
+    )
+    .expect("should parse");
+    // the first default-src wins
+    assert_eq!(
+        builder.finish_string(),
+        "default-src 'self'; img-src https: data:; upgrade-insecure-requests"
+    );
+
+    assert_eq!(
+        CspHeaderBuilder::from_header_str("img-src 'self'; foo-src 'self'").map(|_| ()),
+        Err(ParseError::UnknownDirective {
+            name: "foo-src".to_string()
+        })
+    );
+}
+
+#[test]
+fn test_require_trusted_types_for() {
+    let policy = "require-trusted-types-for 'script'";
+    for builder in [
+        CspHeaderBuilder::from_header_str(policy),
+        CspHeaderBuilder::from_header_str_strict(policy),
+    ] {
+        let builder = builder.expect("should parse");
+        assert_eq!(
+            builder
+                .directive_map
+                .get(&CspDirectiveType::RequireTrustedTypesFor),
+            Some(&vec![CspValue::ScriptSink])
+        );
+        assert_eq!(builder.finish_string(), policy);
+    }
+
+    // unknown sinks are kept, unless parsing strictly
+    let policy = "require-trusted-types-for 'foo'";
+    assert_eq!(
+        CspHeaderBuilder::from_header_str(policy)
+            .expect("should parse")
+            .directive_map
+            .get(&CspDirectiveType::RequireTrustedTypesFor),
+        Some(&vec![CspValue::OtherSink {
+            value: "foo".to_string()
+        }])
+    );
+    assert!(matches!(
+        CspHeaderBuilder::from_header_str_strict(policy),
+        Err(ParseError::InvalidValue { value, .. }) if value == "'foo'"
+    ));
+    assert!(CspHeaderBuilder::from_header_str("require-trusted-types-for script").is_err());
+
+    // and sinks load from config too
+    let builder = CspHeaderBuilder::try_from_map(HashMap::from([(
+        "require-trusted-types-for".to_string(),
+        vec!["'script'".to_string(), "'foo'".to_string()],
+    )]))
+    .expect("should load");
+    assert_eq!(
+        builder
+            .directive_map
+            .get(&CspDirectiveType::RequireTrustedTypesFor),
+        Some(&vec![
+            CspValue::ScriptSink,
+            CspValue::OtherSink {
+                value: "foo".to_string()
+            }
+        ])
+    );
+}
+
+#[test]
+fn test_unknown_directive_suggestion() {
+    let err = CspHeaderBuilder::from_header_str("scrpit-src 'self'").expect_err("should fail");
+    assert_eq!(err.suggestion(), Some(CspDirectiveType::ScriptSource));
+    assert_eq!(
+        err.to_string(),
+        r#"unknown directive "scrpit-src", did you mean `script-src`?"#
+    );
+
+    let err = CspHeaderBuilder::from_header_str("nonsense 'self'").expect_err("should fail");
+    assert_eq!(err.suggestion(), None);
+    assert_eq!(err.to_string(), r#"unknown directive "nonsense""#);
+}
+
+#[test]
+fn test_from_header_str_lossy() {
+    let (builder, errors) = CspHeaderBuilder::from_header_str_lossy(
+        "default-src 'self'; script-src 'self' 'unsafe-inlined'; scrpit-src 'self'; img-src data:",
+    );
+    assert_eq!(builder.finish_string(), "default-src 'self'; img-src data:");
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(
+        &errors[0],
+        ParseError::InvalidValue { value, .. } if value == "'unsafe-inlined'"
+    ));
+    assert_eq!(
+        errors[1],
+        ParseError::UnknownDirective {
+            name: "scrpit-src".to_string()
+        }
+    );
+
+    let (builder, errors) = CspHeaderBuilder::from_header_str_lossy("default-src 'none'");
+    assert_eq!(builder.finish_string(), "default-src 'none'");
+    assert!(errors.is_empty());
+}
+
+#[test]
+#[cfg(feature = "round-trip-check")]
+fn test_round_trip_check() {
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![
+                CspValue::SelfSite,
+                CspValue::Host {
+                    value: "https://example.com".to_string(),
+                },
+            ],
+        );
+    assert_eq!(
+        builder.finish(),
+        "default-src 'self'; script-src 'self' https://example.com"
+    );
+}
+
+#[test]
+#[cfg(all(feature = "round-trip-check", debug_assertions))]
+#[should_panic(expected = "doesn't parse back into the same policy")]
+fn test_round_trip_check_catches_broken_render() {
+    // renders as `https:`, which parses as CspValue::SchemeHttps
+    CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![CspValue::SchemeOther {
+                value: "https:".to_string(),
+            }],
+        )
+        .finish();
+}
+
+#[test]
+fn test_round_trip_tolerates_placeholders_and_unknowns() {
+    let builder = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![CspValue::NoncePlaceholder],
+        )
+        .add(
+            CspDirectiveType::RequireTrustedTypesFor,
+            vec![CspValue::ScriptSink],
+        )
+        .add_unknown("Future-Directive", "'self'");
+    assert_eq!(
+        builder.finish(),
+        "require-trusted-types-for 'script'; script-src 'none'; future-directive 'self'"
+    );
+}
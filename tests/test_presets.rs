@@ -0,0 +1,178 @@
+use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue, Nonce, SandboxToken, Severity};
+
+#[test]
+fn test_web_app() {
+    let builder = CspHeaderBuilder::web_app(
+        vec![CspValue::SelfSite],
+        vec![CspValue::SelfSite, CspValue::UnsafeInline],
+        vec![CspValue::SelfSite, CspValue::SchemeData],
+    );
+    assert_eq!(builder.directive_map.len(), 4);
+    assert_eq!(
+        builder.directive_map.get(&CspDirectiveType::DefaultSrc),
+        Some(&vec![CspValue::SelfSite])
+    );
+    assert_eq!(
+        builder.finish(),
+        "default-src 'self'; img-src 'self' data:; script-src 'self'; style-src 'self' 'unsafe-inline'"
+    );
+}
+
+#[test]
+fn test_api_only() {
+    assert_eq!(
+        CspHeaderBuilder::api_only().finish(),
+        "base-uri 'none'; default-src 'none'; form-action 'none'; frame-ancestors 'none'"
+    );
+    assert!(CspHeaderBuilder::api_only().validate().is_empty());
+}
+
+#[test]
+fn test_hash_only() {
+    let nonce = Nonce::new("abc123");
+    let builder = CspHeaderBuilder::hash_only(Some(&nonce));
+    assert_eq!(
+        builder.finish_string(),
+        "base-uri 'none'; default-src 'none'; object-src 'none'; script-src 'nonce-abc123'; style-src 'nonce-abc123'"
+    );
+    assert!(builder.validate().is_empty());
+    // hosts include `*`
+    assert!(!builder.directive_map.values().flatten().any(|v| matches!(
+        v,
+        CspValue::Host { .. }
+            | CspValue::UnsafeInline
+            | CspValue::UnsafeEval
+            | CspValue::UnsafeHashes
+    )));
+
+    assert_eq!(
+        CspHeaderBuilder::hash_only(None).finish_string(),
+        "base-uri 'none'; default-src 'none'; object-src 'none'"
+    );
+    assert_eq!(builder.assert_integrity_only(), Ok(()));
+    assert_eq!(
+        CspHeaderBuilder::hash_only(None).assert_integrity_only(),
+        Ok(())
+    );
+
+    let weakened = builder.add(
+        CspDirectiveType::ScriptSource,
+        vec![
+            CspValue::Host {
+                value: "*".to_string(),
+            },
+            CspValue::UnsafeInline,
+            CspValue::UnsafeEval,
+        ],
+    );
+    let warnings = weakened
+        .assert_integrity_only()
+        .expect_err("should flag the weakened script-src");
+    assert_eq!(warnings.len(), 3);
+    assert!(warnings
+        .iter()
+        .all(|w| w.directive == Some(CspDirectiveType::ScriptSource)
+            && w.severity == Severity::Error));
+
+    // a style-src missing from the policy falls back to default-src, which is checked instead
+    let fallback = CspHeaderBuilder::hash_only(None)
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SchemeHttps]);
+    let warnings = fallback
+        .assert_integrity_only()
+        .expect_err("should flag default-src");
+    assert_eq!(warnings[0].directive, Some(CspDirectiveType::DefaultSrc));
+
+    let unrestricted =
+        CspHeaderBuilder::new().add(CspDirectiveType::ImgSrc, vec![CspValue::SelfSite]);
+    let warnings = unrestricted
+        .assert_integrity_only()
+        .expect_err("nothing restricts scripts or styles");
+    assert_eq!(
+        warnings
+            .iter()
+            .map(|w| w.directive)
+            .collect::<Vec<Option<CspDirectiveType>>>(),
+        vec![
+            Some(CspDirectiveType::ScriptSource),
+            Some(CspDirectiveType::ScriptSourceAttr),
+            Some(CspDirectiveType::ScriptSourceElem),
+            Some(CspDirectiveType::StyleSource),
+            Some(CspDirectiveType::StyleSourceAttr),
+            Some(CspDirectiveType::StyleSourceElem),
+        ]
+    );
+
+    // every problem is reported at once: the three style directives with nothing to fall back
+    // to, and the 'self' which script-src-elem and script-src-attr fall back to
+    let warnings = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite])
+        .assert_integrity_only()
+        .expect_err("should flag everything");
+    assert_eq!(warnings.len(), 4);
+    assert_eq!(
+        warnings.last().map(|w| w.message.as_str()),
+        Some("'self': 'self' allows any script or style from the same origin")
+    );
+}
+
+#[test]
+fn test_with_legacy_fallbacks() {
+    let nonce = Nonce::new("abc123");
+    let builder = CspHeaderBuilder::hash_only(Some(&nonce))
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![CspValue::StrictDynamic],
+        )
+        .with_legacy_fallbacks();
+    assert_eq!(
+        builder.directive_map.get(&CspDirectiveType::ScriptSource),
+        Some(&vec![
+            CspValue::from(&nonce),
+            CspValue::StrictDynamic,
+            CspValue::UnsafeInline,
+            CspValue::SchemeHttps,
+            CspValue::SchemeHttp,
+        ])
+    );
+    assert!(builder
+        .finish_string()
+        .contains("script-src 'strict-dynamic' 'unsafe-inline' https: http: 'nonce-abc123'"));
+    // modern browsers ignore the fallbacks, so nothing gets weaker
+    assert!(builder.validate().is_empty());
+
+    // without a nonce or 'strict-dynamic' there's nothing to fall back from
+    let plain = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite])
+        .with_legacy_fallbacks();
+    assert_eq!(plain.finish_string(), "script-src 'self'");
+}
+
+#[test]
+fn test_sandboxed_content() {
+    let builder = CspHeaderBuilder::sandboxed_content();
+    assert_eq!(builder.finish_string(), "default-src 'none'; sandbox");
+    assert!(builder.directive_map[&CspDirectiveType::Sandbox].is_empty());
+    for directive in [
+        CspDirectiveType::ScriptSource,
+        CspDirectiveType::ImgSrc,
+        CspDirectiveType::ConnectSrc,
+    ] {
+        assert_eq!(
+            builder.effective_values(directive),
+            Some((CspDirectiveType::DefaultSrc, &vec![CspValue::None]))
+        );
+    }
+
+    let downloads = builder.add(
+        CspDirectiveType::Sandbox,
+        vec![SandboxToken::AllowDownloads.into()],
+    );
+    assert_eq!(
+        downloads.finish_string(),
+        "default-src 'none'; sandbox allow-downloads"
+    );
+    assert_eq!(
+        "Allow-Scripts".parse::<SandboxToken>(),
+        Ok(SandboxToken::AllowScripts)
+    );
+}
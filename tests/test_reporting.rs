@@ -0,0 +1,106 @@
+use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue, ReportingConfig};
+
+#[test]
+fn test_as_report_only_with_samples() {
+    let enforced = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite])
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SchemeData]);
+    let report_only = enforced.as_report_only_with_samples();
+
+    assert_eq!(
+        report_only.finish_string(),
+        // style-src falls back to default-src, so that gets the sample too
+        "default-src 'self' 'report-sample'; img-src data:; script-src 'self' 'report-sample'"
+    );
+    assert_eq!(
+        enforced.finish_string(),
+        "default-src 'self'; img-src data:; script-src 'self'"
+    );
+}
+
+#[test]
+fn test_reporting_config() {
+    let reporting = ReportingConfig::new("csp-endpoint", "https://example.com/csp-reports");
+    assert_eq!(reporting.directive().to_string(), "report-to csp-endpoint");
+    assert_eq!(
+        reporting.endpoints_header_value(),
+        Ok(r#"csp-endpoint="https://example.com/csp-reports""#
+            .parse()
+            .expect("valid header value"))
+    );
+
+    let builder = reporting.apply_to(
+        CspHeaderBuilder::new().add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite]),
+    );
+    assert_eq!(
+        builder.finish_string(),
+        "default-src 'self'; report-to csp-endpoint"
+    );
+}
+
+#[test]
+fn test_add_report_to_from_report_uri() {
+    let (builder, header) = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ReportUri,
+            vec![CspValue::Host {
+                value: "https://example.com/csp".to_string(),
+            }],
+        )
+        .add_report_to_from_report_uri("csp-endpoint")
+        .expect("valid report-uri");
+    assert_eq!(
+        builder.finish_string(),
+        "default-src 'self'; report-to csp-endpoint; report-uri https://example.com/csp"
+    );
+    assert_eq!(
+        header.expect("should have an endpoints header"),
+        r#"csp-endpoint="https://example.com/csp""#
+    );
+
+    let (builder, header) = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add_report_to_from_report_uri("csp-endpoint")
+        .expect("nothing to convert");
+    assert_eq!(builder.finish_string(), "default-src 'self'");
+    assert!(header.is_none());
+}
+
+#[test]
+fn test_reporting_config_rejects_invalid_header_values() {
+    // quotes and backslashes are escaped, per the structured-field string syntax
+    assert_eq!(
+        ReportingConfig::new("csp", r#"https://example.com/"a\b"#).endpoints_header_value(),
+        Ok(r#"csp="https://example.com/\"a\\b""#
+            .parse()
+            .expect("valid header value"))
+    );
+
+    for group in ["", "CSP", "1csp", "csp endpoint", "csp;x"] {
+        let err = ReportingConfig::new(group, "https://example.com/csp")
+            .endpoints_header_value()
+            .expect_err("invalid group name");
+        assert_eq!(err.directive, CspDirectiveType::ReportTo);
+    }
+    assert!(ReportingConfig::new("csp", "https://example.com/\ncsp")
+        .endpoints_header_value()
+        .is_err());
+
+    let err = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ReportUri,
+            vec![CspValue::Host {
+                value: "https://example.com/\ncsp".to_string(),
+            }],
+        )
+        .add_report_to_from_report_uri("csp-endpoint")
+        .expect_err("the newline can't go in a header");
+    assert_eq!(
+        err.value,
+        CspValue::Host {
+            value: "https://example.com/\ncsp".to_string()
+        }
+    );
+}
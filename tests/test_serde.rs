@@ -0,0 +1,23 @@
+#![cfg(feature = "serde")]
+
+use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue};
+
+#[test]
+fn test_postcard_round_trip() {
+    let builder = CspHeaderBuilder::from_header_str(
+        "default-src 'self'; script-src 'self' 'nonce-abc123' 'sha256-xyz=' https://cdn.example.com; \
+         img-src https: data: blob:; require-trusted-types-for 'script'; upgrade-insecure-requests",
+    )
+    .expect("should parse")
+    .add(CspDirectiveType::StyleSource, vec![CspValue::NoncePlaceholder])
+    .add_unknown("future-src", "'self'")
+    .annotate(CspDirectiveType::ImgSrc, Some(CspValue::SchemeData), "icons");
+
+    let bytes = postcard::to_allocvec(&builder).expect("should serialize");
+    let restored: CspHeaderBuilder = postcard::from_bytes(&bytes).expect("should deserialize");
+
+    assert_eq!(restored, builder);
+    assert_eq!(restored.directive_map, builder.directive_map);
+    assert_eq!(restored.annotations, builder.annotations);
+    assert_eq!(restored.finish_string(), builder.finish_string());
+}
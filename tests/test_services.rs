@@ -0,0 +1,103 @@
+use axum_csp::{CspDirectiveType, CspHeaderBuilder, CspValue, KnownService};
+
+fn host(value: &str) -> CspValue {
+    CspValue::Host {
+        value: value.to_string(),
+    }
+}
+
+#[test]
+fn test_with_service_stripe() {
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite])
+        .with_service(KnownService::Stripe);
+    assert_eq!(
+        builder.directive_map.get(&CspDirectiveType::ScriptSource),
+        Some(&vec![CspValue::SelfSite, host("https://js.stripe.com")])
+    );
+    assert_eq!(
+        builder.directive_map.get(&CspDirectiveType::FrameSrc),
+        Some(&vec![
+            host("https://js.stripe.com"),
+            host("https://hooks.stripe.com")
+        ])
+    );
+    assert_eq!(
+        builder.directive_map.get(&CspDirectiveType::ConnectSrc),
+        Some(&vec![host("https://api.stripe.com")])
+    );
+}
+
+#[test]
+fn test_with_every_service() {
+    let builder = [
+        KnownService::Stripe,
+        KnownService::GoogleAnalytics,
+        KnownService::Sentry,
+        KnownService::Intercom,
+    ]
+    .into_iter()
+    .fold(CspHeaderBuilder::new(), |builder, service| {
+        builder.with_service(service)
+    });
+    // once in script-src and once in frame-src
+    assert_eq!(
+        builder
+            .finish_string()
+            .matches("https://js.stripe.com")
+            .count(),
+        2
+    );
+    assert!(builder
+        .referenced_hosts()
+        .contains(&"https://*.ingest.sentry.io".to_string()));
+}
+
+#[test]
+fn test_service_preview() {
+    assert_eq!(
+        KnownService::Stripe.required_additions(),
+        vec![
+            (
+                CspDirectiveType::ConnectSrc,
+                vec![host("https://api.stripe.com")]
+            ),
+            (
+                CspDirectiveType::FrameSrc,
+                vec![
+                    host("https://js.stripe.com"),
+                    host("https://hooks.stripe.com")
+                ]
+            ),
+            (
+                CspDirectiveType::ScriptSource,
+                vec![host("https://js.stripe.com")]
+            ),
+        ]
+    );
+
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![CspValue::SelfSite, host("https://js.stripe.com")],
+        );
+    let preview = KnownService::Stripe.preview(&builder);
+    assert_eq!(
+        preview.added_directives,
+        vec![CspDirectiveType::ConnectSrc, CspDirectiveType::FrameSrc]
+    );
+    assert_eq!(
+        preview.added_values,
+        vec![
+            (CspDirectiveType::ConnectSrc, host("https://api.stripe.com")),
+            (CspDirectiveType::FrameSrc, host("https://hooks.stripe.com")),
+            (CspDirectiveType::FrameSrc, host("https://js.stripe.com")),
+        ]
+    );
+    assert!(preview.removed_values.is_empty());
+    // nothing changes until it's added
+    assert!(!builder
+        .directive_map
+        .contains_key(&CspDirectiveType::ConnectSrc));
+}
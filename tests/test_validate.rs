@@ -0,0 +1,365 @@
+use axum_csp::{
+    CspDirectiveType, CspHeaderBuilder, CspSchema, CspValidator, CspValue, CspWarning, Severity,
+};
+
+#[test]
+fn test_validate_unsafe_inline_only() {
+    let builder =
+        CspHeaderBuilder::new().add(CspDirectiveType::ScriptSource, vec![CspValue::UnsafeInline]);
+    let warnings = builder.validate();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Error);
+    assert_eq!(warnings[0].directive, Some(CspDirectiveType::ScriptSource));
+
+    // falls back to default-src
+    let builder = CspHeaderBuilder::new().add(
+        CspDirectiveType::DefaultSrc,
+        vec![CspValue::SelfSite, CspValue::UnsafeInline],
+    );
+    assert_eq!(
+        builder.validate()[0].directive,
+        Some(CspDirectiveType::DefaultSrc)
+    );
+}
+
+#[test]
+fn test_validate_nonce_strict_dynamic() {
+    let builder = CspHeaderBuilder::new().add(
+        CspDirectiveType::ScriptSource,
+        vec![
+            CspValue::Nonce {
+                value: "x".to_string(),
+            },
+            CspValue::StrictDynamic,
+        ],
+    );
+    assert!(builder.validate().is_empty());
+}
+
+#[test]
+fn test_effective_values() {
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::None])
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite]);
+    assert_eq!(
+        builder.effective_values(CspDirectiveType::ScriptSourceElem),
+        Some((CspDirectiveType::ScriptSource, &vec![CspValue::SelfSite]))
+    );
+    assert_eq!(
+        builder.effective_values(CspDirectiveType::WorkerSource),
+        Some((CspDirectiveType::ScriptSource, &vec![CspValue::SelfSite]))
+    );
+    assert_eq!(
+        builder.effective_values(CspDirectiveType::FencedFrameSrc),
+        Some((CspDirectiveType::DefaultSrc, &vec![CspValue::None]))
+    );
+    assert_eq!(builder.effective_values(CspDirectiveType::BaseUri), None);
+}
+
+#[test]
+fn test_validate_prefetch_src_deprecated() {
+    assert!(CspDirectiveType::PrefetchSrc.is_deprecated());
+    assert!(!CspDirectiveType::ConnectSrc.is_deprecated());
+
+    let builder =
+        CspHeaderBuilder::new().add(CspDirectiveType::PrefetchSrc, vec![CspValue::SelfSite]);
+    let warnings = builder.validate();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert_eq!(warnings[0].directive, Some(CspDirectiveType::PrefetchSrc));
+    assert!(warnings[0].message.contains("connect-src"));
+}
+
+#[test]
+fn test_validate_max_values() {
+    let builder = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ImgSrc,
+            vec![
+                CspValue::SelfSite,
+                CspValue::SchemeData,
+                CspValue::SchemeHttps,
+            ],
+        )
+        .add(
+            CspDirectiveType::FontSrc,
+            vec![CspValue::SelfSite, CspValue::SchemeData],
+        );
+    // no limit by default
+    assert!(builder.validate().is_empty());
+
+    let options = axum_csp::ValidationOptions::default()
+        .with_max_values(2)
+        .with_directive_max_values(CspDirectiveType::FontSrc, 1);
+    let warnings = builder.validate_with_options(&options);
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].directive, Some(CspDirectiveType::FontSrc));
+    assert_eq!(warnings[1].directive, Some(CspDirectiveType::ImgSrc));
+    assert!(warnings[1].message.contains("maximum of 2"));
+}
+
+#[test]
+fn test_assert_cacheable() {
+    let builder = CspHeaderBuilder::new().add(
+        CspDirectiveType::ScriptSource,
+        vec![CspValue::SelfSite, CspValue::NoncePlaceholder],
+    );
+    assert_eq!(builder.assert_cacheable(), Ok(()));
+
+    let errors = builder
+        .add(
+            CspDirectiveType::StyleSource,
+            vec![CspValue::Nonce {
+                value: "abc".to_string(),
+            }],
+        )
+        .assert_cacheable()
+        .expect_err("has a nonce");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].directive, Some(CspDirectiveType::StyleSource));
+}
+
+#[test]
+fn test_validate_misplaced_keyword() {
+    let builder = CspHeaderBuilder::new().add(
+        CspDirectiveType::ImgSrc,
+        vec![CspValue::SelfSite, CspValue::StrictDynamic],
+    );
+    let warnings = builder.validate();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].directive, Some(CspDirectiveType::ImgSrc));
+    assert!(warnings[0].message.contains("'strict-dynamic'"));
+}
+
+#[test]
+fn test_validate_broad_connect_src() {
+    let broad = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ConnectSrc,
+            vec![CspValue::Host {
+                value: "*".to_string(),
+            }],
+        );
+    let warnings = broad.validate();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert_eq!(warnings[0].directive, Some(CspDirectiveType::ConnectSrc));
+
+    // default-src is where connect-src falls back to
+    let fallback =
+        CspHeaderBuilder::new().add(CspDirectiveType::DefaultSrc, vec![CspValue::SchemeHttps]);
+    assert_eq!(
+        fallback.validate()[0].directive,
+        Some(CspDirectiveType::DefaultSrc)
+    );
+
+    let specific = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ConnectSrc,
+            vec![CspValue::Host {
+                value: "https://api.example.com".to_string(),
+            }],
+        );
+    assert!(specific.validate().is_empty());
+}
+
+#[test]
+fn test_validate_deprecated_without_replacements() {
+    let builder = CspHeaderBuilder::from_header_str(
+        "default-src 'self'; report-uri https://example.com/csp; block-all-mixed-content",
+    )
+    .expect("should parse");
+    let warnings = builder.validate();
+    // one warning per deprecated directive, plus the summary
+    assert_eq!(warnings.len(), 3);
+    let summary = warnings
+        .iter()
+        .find(|w| w.directive.is_none())
+        .expect("summary warning");
+    assert_eq!(
+        summary.message,
+        "policy relies on deprecated directives without their replacements, add \
+         upgrade-insecure-requests (for block-all-mixed-content), report-to (for report-uri)"
+    );
+
+    let migrated = builder
+        .add(CspDirectiveType::UpgradeInsecureRequests, vec![])
+        .add(
+            CspDirectiveType::ReportTo,
+            vec![CspValue::Host {
+                value: "csp".to_string(),
+            }],
+        );
+    assert!(!migrated.validate().iter().any(|w| w.directive.is_none()));
+}
+
+#[test]
+fn test_assert_production_safe() {
+    let production = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ConnectSrc,
+            vec![CspValue::Host {
+                value: "https://api.example.com".to_string(),
+            }],
+        );
+    assert_eq!(production.assert_production_safe(), Ok(()));
+
+    let dev = production.with_dev_overrides(
+        CspHeaderBuilder::new()
+            .add(CspDirectiveType::ScriptSource, vec![CspValue::UnsafeEval])
+            .add(
+                CspDirectiveType::ConnectSrc,
+                vec![CspValue::Host {
+                    value: "ws://localhost:3000".to_string(),
+                }],
+            ),
+    );
+    let errors = dev.assert_production_safe().expect_err("should fail");
+    assert_eq!(errors.len(), 2);
+    assert!(errors.iter().all(|e| e.severity == Severity::Error));
+    assert_eq!(errors[0].directive, Some(CspDirectiveType::ConnectSrc));
+    assert_eq!(errors[1].directive, Some(CspDirectiveType::ScriptSource));
+    assert!(errors[1].message.contains("'unsafe-eval'"));
+}
+
+#[test]
+fn test_validate_report_sample_privacy() {
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![CspValue::SelfSite, CspValue::ReportSample],
+        );
+    let warnings = builder.validate();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Info);
+    assert_eq!(warnings[0].directive, Some(CspDirectiveType::ScriptSource));
+    assert!(warnings[0].message.contains("disclose"));
+}
+
+#[test]
+fn test_validate_inline_styles_with_nonce_scripts() {
+    let nonce = CspValue::Nonce {
+        value: "abc".to_string(),
+    };
+    let mixed = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![nonce.clone(), CspValue::StrictDynamic],
+        )
+        .add(
+            CspDirectiveType::StyleSource,
+            vec![CspValue::SelfSite, CspValue::UnsafeInline],
+        );
+    let warnings = mixed.validate();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Info);
+    assert_eq!(warnings[0].directive, Some(CspDirectiveType::StyleSource));
+
+    let consistent = mixed.add(CspDirectiveType::StyleSource, vec![nonce]);
+    assert!(consistent.validate().is_empty());
+}
+
+#[test]
+fn test_validate_against_schema() {
+    let schema = CspSchema::new()
+        .allow(CspDirectiveType::DefaultSrc, &["'self'", "'none'"])
+        .expect("valid patterns")
+        .allow(
+            CspDirectiveType::ScriptSource,
+            &["'self'", r"https://[a-z]+\.example\.com"],
+        )
+        .expect("valid patterns");
+
+    let ok = CspHeaderBuilder::from_header_str(
+        "default-src 'self'; script-src 'self' https://cdn.example.com",
+    )
+    .expect("should parse");
+    assert_eq!(ok.validate_against_schema(&schema), Ok(()));
+
+    let bad = CspHeaderBuilder::from_header_str(
+        "default-src 'self'; script-src https://cdn.example.com.evil.com; frame-src *",
+    )
+    .expect("should parse");
+    let violations = bad
+        .validate_against_schema(&schema)
+        .expect_err("should violate the schema");
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0].directive, Some(CspDirectiveType::FrameSrc));
+    assert_eq!(violations[0].value, None);
+    assert_eq!(
+        violations[1].directive,
+        Some(CspDirectiveType::ScriptSource)
+    );
+    assert_eq!(
+        violations[1].value,
+        Some(CspValue::Host {
+            value: "https://cdn.example.com.evil.com".to_string()
+        })
+    );
+
+    let config = std::collections::HashMap::from([
+        ("default-src".to_string(), vec!["'self'".to_string()]),
+        ("scritp-src".to_string(), vec!["(".to_string()]),
+    ]);
+    let errors = CspSchema::try_from_map(config).expect_err("should fail");
+    assert_eq!(errors.errors.len(), 2);
+}
+
+/// Only allows hosts on example.com and its subdomains
+struct ExampleHostsOnly;
+
+impl CspValidator for ExampleHostsOnly {
+    fn validate(&self, builder: &CspHeaderBuilder) -> Vec<CspWarning> {
+        let mut res = vec![];
+        for (directive, values) in &builder.directive_map {
+            for value in values {
+                if let CspValue::Host { value } = value {
+                    let host = value.split("://").last().unwrap_or(value);
+                    if host != "example.com" && !host.ends_with(".example.com") {
+                        res.push(CspWarning::new(
+                            Severity::Error,
+                            Some(*directive),
+                            format!("{value} isn't on example.com"),
+                        ));
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+#[test]
+fn test_validate_with_custom_validator() {
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ConnectSrc,
+            vec![CspValue::Host {
+                value: "https://api.example.com".to_string(),
+            }],
+        )
+        .add(
+            CspDirectiveType::ImgSrc,
+            vec![CspValue::Host {
+                value: "https://evil.com".to_string(),
+            }],
+        );
+    assert!(builder.validate().is_empty());
+
+    let validators: Vec<Box<dyn CspValidator>> = vec![Box::new(ExampleHostsOnly)];
+    let warnings = builder.validate_with(&validators);
+    assert_eq!(
+        warnings,
+        vec![CspWarning::new(
+            Severity::Error,
+            Some(CspDirectiveType::ImgSrc),
+            "https://evil.com isn't on example.com"
+        )]
+    );
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "watch")]
+
+use axum_csp::watch::CspConfigWatcher;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn policy_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("axum-csp-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let path = dir.join("csp.txt");
+    std::fs::write(&path, contents).expect("write policy file");
+    path
+}
+
+/// Wait for the watcher to pick up a change
+fn wait_for(watcher: &CspConfigWatcher, expected: &str) -> String {
+    let start = Instant::now();
+    loop {
+        let current = watcher.handle().load().finish_string();
+        if current == expected || start.elapsed() > Duration::from_secs(10) {
+            return current;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn test_watcher_reloads_on_change() {
+    let path = policy_file("reload", "default-src 'self'");
+    let watcher = CspConfigWatcher::new(&path).expect("watcher");
+    assert_eq!(
+        watcher.handle().load().finish_string(),
+        "default-src 'self'"
+    );
+
+    std::fs::write(&path, "default-src 'none';\nimg-src https:").expect("update policy file");
+    assert_eq!(
+        wait_for(&watcher, "default-src 'none'; img-src https:"),
+        "default-src 'none'; img-src https:"
+    );
+
+    // a broken file keeps the last good policy
+    std::fs::write(&path, "default-src 'bogus'").expect("break policy file");
+    std::thread::sleep(Duration::from_millis(500));
+    assert!(watcher.reload().is_err());
+    assert_eq!(
+        watcher.handle().load().finish_string(),
+        "default-src 'none'; img-src https:"
+    );
+
+    std::fs::remove_dir_all(path.parent().expect("parent")).expect("cleanup");
+}
+
+#[test]
+fn test_watcher_rejects_invalid_initial_policy() {
+    let path = policy_file("invalid", "scrpit-src 'self'");
+    assert!(CspConfigWatcher::new(&path).is_err());
+    std::fs::remove_dir_all(path.parent().expect("parent")).expect("cleanup");
+}
+
+#[tokio::test]
+async fn test_watched_middleware_serves_current_policy() {
+    use axum::body::Body;
+    use axum::http::header::CONTENT_SECURITY_POLICY;
+    use axum::http::Request;
+    use axum::middleware::from_fn_with_state;
+    use axum::routing::get;
+    use axum::Router;
+    use axum_csp::watch::{csp_watched_middleware, CspPolicyHandle};
+    use axum_csp::CspHeaderBuilder;
+    use tower::ServiceExt;
+
+    let handle = CspPolicyHandle::new(
+        CspHeaderBuilder::from_header_str("default-src 'self'").expect("policy"),
+    );
+    let router = Router::new()
+        .route("/", get(|| async { "hello" }))
+        .layer(from_fn_with_state(handle.clone(), csp_watched_middleware));
+
+    for expected in ["default-src 'self'", "default-src 'none'"] {
+        handle.store(CspHeaderBuilder::from_header_str(expected).expect("policy"));
+        // rendered when stored, not per request
+        assert_eq!(handle.header(), expected);
+        let response = router
+            .clone()
+            .oneshot(Request::get("/").body(Body::empty()).expect("request"))
+            .await
+            .expect("response");
+        assert_eq!(
+            response
+                .headers()
+                .get(CONTENT_SECURITY_POLICY)
+                .expect("header"),
+            expected
+        );
+    }
+}
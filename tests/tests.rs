@@ -1,5 +1,7 @@
 use axum::http::HeaderValue;
-use axum_csp::{CspDirective, CspDirectiveType, CspHeaderBuilder, CspUrlMatcher, CspValue};
+use axum_csp::{
+    CspDirective, CspDirectiveType, CspHeaderBuilder, CspUrlMatcher, CspValue, CspWarning, Severity,
+};
 use regex::RegexSet;
 
 #[test]
@@ -35,6 +37,7 @@ fn test_example() {
 }
 
 #[test]
+#[allow(clippy::cmp_owned)]
 fn test_directive_to_string() {
     let directive: CspDirective = CspDirective {
         directive_type: CspDirectiveType::ImgSrc,
@@ -58,3 +61,305 @@ fn test_directives_to_string() {
     let expected = HeaderValue::from_static("default-src https:; img-src 'self'");
     assert_eq!(cspset, expected);
 }
+
+#[test]
+fn test_is_experimental() {
+    assert!(CspValue::UnsafeAllowRedirects.is_experimental());
+    assert!(!CspValue::SelfSite.is_experimental());
+    assert!(CspDirectiveType::FencedFrameSrc.is_experimental());
+    assert!(!CspDirectiveType::ImgSrc.is_experimental());
+}
+
+#[test]
+fn test_render_checked() {
+    let good = CspDirective::from(
+        CspDirectiveType::ImgSrc,
+        vec![CspValue::SelfSite, CspValue::SchemeHttps],
+    );
+    assert_eq!(good.render_checked(), Ok(good.to_string()));
+
+    let bad_host = CspValue::Host {
+        value: "exämple.com".to_string(),
+    };
+    let bad = CspDirective::from(
+        CspDirectiveType::ImgSrc,
+        vec![CspValue::SelfSite, bad_host.clone()],
+    );
+    let err = bad.render_checked().expect_err("should fail");
+    assert_eq!(err.directive, CspDirectiveType::ImgSrc);
+    assert_eq!(err.value, bad_host);
+    assert!(err.reason.contains("'ä'"));
+
+    let spaced = CspDirective::from(
+        CspDirectiveType::ImgSrc,
+        vec![CspValue::Host {
+            value: "a.com b.com".to_string(),
+        }],
+    );
+    assert!(spaced
+        .render_checked()
+        .expect_err("should fail")
+        .reason
+        .contains("whitespace"));
+}
+
+#[test]
+fn test_shared_headers() {
+    let matchers = vec![
+        CspUrlMatcher::default_self(RegexSet::new([r#"^/a"#]).expect("Failed to build a regex")),
+        CspUrlMatcher::default_self(RegexSet::new([r#"^/b"#]).expect("Failed to build a regex")),
+        CspUrlMatcher::new(RegexSet::new([r#"^/c"#]).expect("Failed to build a regex"))
+            .with_directive(CspDirective::from(
+                CspDirectiveType::ImgSrc,
+                vec![CspValue::SchemeData],
+            ))
+            .to_owned(),
+    ];
+    assert_eq!(CspUrlMatcher::distinct_policies(&matchers), 2);
+
+    let headers = CspUrlMatcher::shared_headers(&matchers);
+    assert_eq!(headers.len(), 3);
+    assert!(std::sync::Arc::ptr_eq(&headers[0], &headers[1]));
+    assert!(!std::sync::Arc::ptr_eq(&headers[0], &headers[2]));
+    assert_eq!(*headers[2], "img-src data:;");
+}
+
+#[test]
+fn test_allowed_keywords() {
+    let cases: &[(CspDirectiveType, &[&str])] = &[
+        (
+            CspDirectiveType::ScriptSource,
+            &[
+                "'strict-dynamic'",
+                "'report-sample'",
+                "'unsafe-inline'",
+                "'unsafe-eval'",
+                "'wasm-unsafe-eval'",
+                "'unsafe-hashes'",
+                "'inline-speculation-rules'",
+                "'trusted-types-eval'",
+            ],
+        ),
+        (
+            CspDirectiveType::StyleSource,
+            &["'report-sample'", "'unsafe-inline'", "'unsafe-hashes'"],
+        ),
+        (CspDirectiveType::ImgSrc, &[]),
+        (CspDirectiveType::FrameAncestors, &[]),
+        (CspDirectiveType::NavigateTo, &["'unsafe-allow-redirects'"]),
+    ];
+    for (directive, expected) in cases {
+        assert_eq!(directive.allowed_keywords(), *expected, "{directive}");
+    }
+}
+
+#[test]
+fn test_as_fields() {
+    let builder = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![
+                CspValue::SchemeHttps,
+                CspValue::SelfSite,
+                CspValue::SelfSite,
+            ],
+        )
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::None])
+        .add(CspDirectiveType::UpgradeInsecureRequests, vec![]);
+    assert_eq!(
+        builder.as_fields(),
+        vec![
+            ("csp.default-src".to_string(), "'none'".to_string()),
+            ("csp.script-src".to_string(), "'self' https:".to_string()),
+            ("csp.upgrade-insecure-requests".to_string(), "".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_map_values() {
+    let builder = CspHeaderBuilder::new()
+        .add(
+            CspDirectiveType::ScriptSource,
+            vec![CspValue::SelfSite, CspValue::UnsafeInline],
+        )
+        .add(
+            CspDirectiveType::StyleSource,
+            vec![CspValue::UnsafeInline, CspValue::SchemeHttps],
+        )
+        .add(CspDirectiveType::ImgSrc, vec![CspValue::SchemeData])
+        .map_values(|_, values| values.retain(|v| v != &CspValue::UnsafeInline))
+        .map_values(|directive, values| {
+            if directive == CspDirectiveType::ScriptSource {
+                values.push(CspValue::Nonce {
+                    value: "abc".to_string(),
+                });
+            }
+        });
+    assert_eq!(
+        builder.finish_string(),
+        "img-src data:; script-src 'self' 'nonce-abc'; style-src https:"
+    );
+}
+
+#[test]
+fn test_matchers_from_routes() {
+    let policy = |value: CspValue| {
+        vec![CspDirective::from(
+            CspDirectiveType::DefaultSrc,
+            vec![value],
+        )]
+    };
+    let matchers = CspUrlMatcher::from_routes(&[
+        ("/users/{id}", policy(CspValue::None)),
+        ("/legacy/:id/edit", policy(CspValue::None)),
+        ("/static/{*path}", policy(CspValue::SelfSite)),
+        ("/", policy(CspValue::SelfSite)),
+    ])
+    .expect("valid routes");
+    assert_eq!(matchers.len(), 4);
+
+    for (path, expected) in [
+        ("/users/42", Some(0)),
+        ("/users/42/extra", None),
+        ("/users/", None),
+        ("/legacy/7/edit", Some(1)),
+        ("/static/css/site.css", Some(2)),
+        ("/", Some(3)),
+        ("/nope", None),
+    ] {
+        assert_eq!(
+            matchers.iter().position(|m| m.is_match(path)),
+            expected,
+            "{path}"
+        );
+    }
+
+    assert_eq!(
+        CspUrlMatcher::from_routes(&[("/files/{*path}/edit", vec![])])
+            .map(|_| ())
+            .map_err(|e| e.reason),
+        Err("wildcards can only be the last segment".to_string())
+    );
+    assert!(CspUrlMatcher::from_routes(&[("users/{id", vec![])]).is_err());
+    assert!(CspUrlMatcher::from_routes(&[("/users/{id", vec![])]).is_err());
+}
+
+#[test]
+fn test_add_conditional() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let enabled = Arc::new(AtomicBool::new(true));
+    let flag = enabled.clone();
+    let widget = CspValue::Host {
+        value: "https://widget.example.com".to_string(),
+    };
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::ScriptSource, vec![CspValue::SelfSite])
+        .add_conditional(
+            CspDirectiveType::ScriptSource,
+            vec![widget.clone()],
+            move || flag.load(Ordering::SeqCst),
+        );
+
+    assert_eq!(
+        builder.clone().finish(),
+        "script-src 'self' https://widget.example.com"
+    );
+    assert!(
+        builder.resolve_conditions().directive_map[&CspDirectiveType::ScriptSource]
+            .contains(&widget)
+    );
+
+    enabled.store(false, Ordering::SeqCst);
+    assert_eq!(builder.clone().finish(), "script-src 'self'");
+    assert!(builder
+        .resolve_conditions()
+        .conditional_directives
+        .is_empty());
+}
+
+#[test]
+fn test_add_trims_hosts() {
+    let builder = CspHeaderBuilder::new().add(
+        CspDirectiveType::ScriptSource,
+        vec![
+            CspValue::Host {
+                value: " https://example.com\t".to_string(),
+            },
+            CspValue::Host {
+                value: "https://example.com".to_string(),
+            },
+            CspValue::SchemeOther {
+                value: "blob: ".to_string(),
+            },
+            CspValue::Host {
+                value: "  ".to_string(),
+            },
+        ],
+    );
+    assert_eq!(
+        builder.directive_map[&CspDirectiveType::ScriptSource],
+        vec![
+            CspValue::Host {
+                value: "https://example.com".to_string()
+            },
+            CspValue::SchemeOther {
+                value: "blob:".to_string()
+            },
+        ]
+    );
+    assert_eq!(
+        builder.validate(),
+        vec![
+            CspWarning::new(
+                Severity::Warning,
+                Some(CspDirectiveType::ScriptSource),
+                "trimmed whitespace from \" https://example.com\\t\""
+            ),
+            CspWarning::new(
+                Severity::Warning,
+                Some(CspDirectiveType::ScriptSource),
+                "trimmed whitespace from \"blob: \""
+            ),
+            CspWarning::new(
+                Severity::Warning,
+                Some(CspDirectiveType::ScriptSource),
+                "dropped the empty value \"  \""
+            ),
+        ]
+    );
+    assert_eq!(builder.finish(), "script-src https://example.com blob:");
+}
+
+#[test]
+fn test_finish_bytes() {
+    let builder = CspHeaderBuilder::new()
+        .add(CspDirectiveType::DefaultSrc, vec![CspValue::SelfSite])
+        .add(
+            CspDirectiveType::ImgSrc,
+            vec![CspValue::Host {
+                value: "https://example.com".to_string(),
+            }],
+        );
+    assert_eq!(
+        builder.clone().finish_bytes(),
+        Ok(builder.finish_string().into_bytes())
+    );
+
+    let unicode = CspHeaderBuilder::new().add(
+        CspDirectiveType::ImgSrc,
+        vec![CspValue::Host {
+            value: "https://exämple.com".to_string(),
+        }],
+    );
+    let err = unicode.finish_bytes().expect_err("should reject non-ASCII");
+    assert_eq!(
+        err,
+        axum_csp::FinishError {
+            character: 'ä',
+            position: 18,
+        }
+    );
+}
@@ -0,0 +1,5 @@
+fn main() {
+    let _builder = axum_csp::csp! {
+        "img-src" => ["https://example.com\u{a0}"],
+    };
+}
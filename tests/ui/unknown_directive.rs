@@ -0,0 +1,5 @@
+fn main() {
+    let _builder = axum_csp::csp! {
+        "scirpt-src" => ["'self'"],
+    };
+}